@@ -0,0 +1,104 @@
+//! 焼きなましで、更新後のモデルを受理するかを決める確率関数を提供
+//!
+//! いずれもrunやrun_with_operatorsのprob_funcとして用いる。
+//! basic_prob_func以外は0か1を返し、確率によらず受理するかが決まる。
+//! また、閾値や水位を下げていくための温度関数を提供する。
+
+/// 確率関数の戻り値に変換する
+fn accept_if(accepted: bool) -> f32 {
+    if accepted {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// 山登り法の確率関数
+/// スコアが悪化しない場合のみ更新する
+/// 温度は用いない
+pub fn hill_climbing_prob_func(score_now: f32, score_next: f32, _temp: f32) -> f32 {
+    accept_if(score_next <= score_now)
+}
+
+/// 閾値受理法の確率関数
+/// 温度を閾値として、スコアの悪化幅が閾値以下の場合に更新する
+pub fn threshold_prob_func(score_now: f32, score_next: f32, temp: f32) -> f32 {
+    accept_if(score_next - score_now <= temp)
+}
+
+/// 大洪水法の確率関数
+/// 温度を水位として、スコアが水位以下か悪化しない場合に更新する
+pub fn great_deluge_prob_func(score_now: f32, score_next: f32, temp: f32) -> f32 {
+    accept_if(score_next <= temp || score_next <= score_now)
+}
+
+/// 閾値受理法と大洪水法の温度関数
+/// 最初のステップで最大温度となり、最後のステップで最低温度となるよう線形に下がる
+pub fn decreasing_temp_func(temp_max: f32, temp_min: f32, step_end: u32, step_now: u32) -> f32 {
+    let r: f32 = step_now as f32 / step_end as f32;
+    temp_max - ((temp_max - temp_min) * r)
+}
+
+/// 遅延受理山登り法の確率関数の状態
+/// 一定ステップ前の現在のスコアを記録し、それより悪化しない場合に更新する
+#[derive(Debug, Clone)]
+pub struct LateAcceptance {
+    history: Vec<f32>,
+    step: usize,
+}
+
+impl LateAcceptance {
+    /// コンストラクタ
+    /// 何ステップ前のスコアと比べるかと、初期のスコアを指定する
+    pub fn new(length: usize, initial_score: f32) -> Self {
+        LateAcceptance {
+            history: vec![initial_score; length.max(1)],
+            step: 0,
+        }
+    }
+
+    /// 確率関数
+    /// 受理した場合は更新後の、受理しなかった場合は更新前のスコアを記録する
+    /// 温度は用いない
+    pub fn prob_func(&mut self, score_now: f32, score_next: f32, _temp: f32) -> f32 {
+        let i = self.step % self.history.len();
+        let accepted = score_next <= score_now || score_next <= self.history[i];
+        self.history[i] = if accepted { score_next } else { score_now };
+        self.step += 1;
+        accept_if(accepted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_and_great_deluge() {
+        assert_eq!(threshold_prob_func(10.0, 15.0, 5.0), 1.0);
+        assert_eq!(threshold_prob_func(10.0, 16.0, 5.0), 0.0);
+        assert_eq!(great_deluge_prob_func(10.0, 15.0, 20.0), 1.0);
+        assert_eq!(great_deluge_prob_func(10.0, 15.0, 12.0), 0.0);
+        assert_eq!(great_deluge_prob_func(10.0, 9.0, 0.0), 1.0);
+    }
+
+    /// 最大温度から最低温度へ線形に下がるケース
+    #[test]
+    fn test_decreasing_temp_func() {
+        assert_eq!(decreasing_temp_func(100.0, 0.0, 10, 0), 100.0);
+        assert_eq!(decreasing_temp_func(100.0, 0.0, 10, 5), 50.0);
+        assert_eq!(decreasing_temp_func(100.0, 0.0, 10, 10), 0.0);
+        assert_eq!(decreasing_temp_func(30.0, 10.0, 4, 1), 25.0);
+    }
+
+    /// 2ステップ前のスコアまでの悪化を受理するケース
+    #[test]
+    fn test_late_acceptance() {
+        let mut la = LateAcceptance::new(2, 10.0);
+        assert_eq!(la.prob_func(10.0, 8.0, 0.0), 1.0);
+        assert_eq!(la.prob_func(8.0, 10.0, 0.0), 1.0);
+        assert_eq!(la.prob_func(10.0, 9.0, 0.0), 1.0);
+        assert_eq!(la.prob_func(9.0, 11.0, 0.0), 0.0);
+        assert_eq!(la.prob_func(9.0, 9.5, 0.0), 0.0);
+    }
+}
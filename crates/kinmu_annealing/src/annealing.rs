@@ -1,3 +1,5 @@
+use super::{OperatorSelector, RunStats};
+
 use rand::Rng;
 
 /// 焼きなましの実行
@@ -40,6 +42,51 @@ pub fn run<M, S, U, E, T, P, R>(
     initial_model: &M,
     step_count: u32,
     mut update: U,
+    eval: E,
+    temp_max: f32,
+    temp_min: f32,
+    temp_func: T,
+    prob_func: P,
+    rng: &mut R,
+) -> (S, M)
+where
+    M: Clone,
+    S: std::cmp::PartialOrd + Copy,
+    U: FnMut(&M, &mut R) -> M,
+    E: FnMut(&M) -> S,
+    T: FnMut(f32, f32, u32, u32) -> f32,
+    P: FnMut(S, S, f32) -> f32,
+    R: Rng,
+{
+    run_with_operators(
+        initial_score,
+        initial_model,
+        step_count,
+        std::slice::from_mut(&mut update),
+        &mut OperatorSelector::new(vec![1.0], false),
+        &mut RunStats::new(step_count, initial_score),
+        eval,
+        temp_max,
+        temp_min,
+        temp_func,
+        prob_func,
+        rng,
+    )
+}
+
+/// 複数の更新関数を用いた焼きなましの実行
+///
+/// 各ステップでselectorが選んだ更新関数を用い、その結果をselectorに記録する。
+/// selectorがadaptiveの場合、選ばれる確率が結果に応じて変化する。
+/// 各ステップの結果と最良のスコアはstatsにも記録し、終了時に最後の最良のスコアを記録する。
+#[allow(clippy::too_many_arguments)]
+pub fn run_with_operators<M, S, U, E, T, P, R>(
+    initial_score: S,
+    initial_model: &M,
+    step_count: u32,
+    updates: &mut [U],
+    selector: &mut OperatorSelector,
+    stats: &mut RunStats<S>,
     mut eval: E,
     temp_max: f32,
     temp_min: f32,
@@ -65,24 +112,33 @@ where
     let mut temp;
 
     for loop_value in 0..step_count {
-        let next_model = update(&current_model, rng);
+        let i = selector.choose(rng);
+        let next_model = updates[i](&current_model, rng);
         let next_score = eval(&next_model);
 
         temp = temp_func(temp_max, temp_min, step_count, loop_value);
 
+        let improved = next_score < current_score;
+
         // スコアが改善または確率でモデルを更新
-        if rng.gen::<f32>() < prob_func(current_score, next_score, temp) {
+        let accepted = rng.gen::<f32>() < prob_func(current_score, next_score, temp);
+        if accepted {
             current_model = next_model.clone();
             current_score = next_score;
         }
 
         // 最良モデルの更新
         // ここは < だとランダムウォークできないため <= にしてある
+        let best = next_score < best_score;
         if next_score <= best_score {
             best_model = next_model;
             best_score = next_score;
         }
+
+        selector.record(i, accepted, improved, best);
+        stats.record(accepted, accepted && improved, best_score);
     }
+    stats.finish(best_score);
 
     (best_score, best_model)
 }
@@ -94,9 +150,40 @@ pub fn basic_temp_func(temp_max: f32, temp_min: f32, step_end: u32, step_now: u3
     temp_max - ((temp_max - temp_min) * r)
 }
 
-/// 標準の確率関数
+/// 標準の確率関数 (メトロポリス基準)
 /// 前後のスコアと温度から、スコアが悪化した場合に更新する確率を返す
 /// スコアが改善した場合、常に1を越える値を返す
 pub fn basic_prob_func(score_now: f32, score_next: f32, temp: f32) -> f32 {
     ((score_now - score_next) / temp).exp()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    /// ステップ数が記録の間隔の倍数でなくても、最後の最良のスコアが記録されるケース
+    #[test]
+    fn test_trace_ends_with_best() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut stats = RunStats::new(3, 10);
+        let (best_score, _) = run_with_operators(
+            10,
+            &10,
+            7,
+            &mut [|x: &i32, _: &mut rand::rngs::StdRng| x - 1],
+            &mut OperatorSelector::new(vec![1.0], false),
+            &mut stats,
+            |x| *x,
+            1.0,
+            0.0,
+            basic_temp_func,
+            |now: i32, next: i32, _| if next <= now { 1.0 } else { 0.0 },
+            &mut rng,
+        );
+        assert_eq!(best_score, 3);
+        assert_eq!(stats.steps(), 7);
+        assert_eq!(stats.trace(), &[10, 7, 4, 3]);
+    }
+}
@@ -1,5 +1,15 @@
-//! 焼きなまし法のアルゴリズムを提供
+//! 焼きなまし法とタブーサーチのアルゴリズムを提供
 
+mod acceptance;
 mod annealing;
+mod operator;
+mod stats;
+mod tabu;
+mod temperature;
 
+pub use acceptance::*;
 pub use annealing::*;
+pub use operator::*;
+pub use stats::*;
+pub use tabu::*;
+pub use temperature::*;
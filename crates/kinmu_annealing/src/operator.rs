@@ -0,0 +1,177 @@
+use rand::Rng;
+
+/// 報酬を反映するまでのステップ数
+const SEGMENT_LENGTH: u32 = 100;
+
+/// 重みの更新で新しい報酬を反映する割合
+const REACTION: f32 = 0.1;
+
+/// 重みの下限
+/// どの操作も選ばれなくなることを防ぐ
+const MIN_WEIGHT: f32 = 0.05;
+
+/// 最良のスコアを更新した場合の報酬
+const REWARD_BEST: f32 = 3.0;
+
+/// 現在のスコアを改善した場合の報酬
+const REWARD_IMPROVED: f32 = 2.0;
+
+/// 改善はしなかったが受理された場合の報酬
+const REWARD_ACCEPTED: f32 = 1.0;
+
+/// 操作ごとの統計
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperatorStats {
+    /// 選ばれた回数
+    pub used: u32,
+    /// 受理された回数
+    pub accepted: u32,
+    /// 現在のスコアを改善した回数
+    pub improved: u32,
+    /// 最終的な選択確率
+    pub probability: f32,
+}
+
+/// 複数の操作から重みに従って1つを選ぶ
+/// adaptiveの場合、受理や改善の結果から重みを適応的に変更する
+#[derive(Debug, Clone)]
+pub struct OperatorSelector {
+    adaptive: bool,
+    weights: Vec<f32>,
+    stats: Vec<OperatorStats>,
+    segment_rewards: Vec<f32>,
+    segment_uses: Vec<u32>,
+    segment_step: u32,
+}
+
+impl OperatorSelector {
+    /// コンストラクタ
+    /// 初期の重みと、重みを適応的に変更するかを指定する
+    pub fn new(weights: Vec<f32>, adaptive: bool) -> Self {
+        let n = weights.len();
+        OperatorSelector {
+            adaptive,
+            weights,
+            stats: vec![OperatorStats::default(); n],
+            segment_rewards: vec![0.0; n],
+            segment_uses: vec![0; n],
+            segment_step: 0,
+        }
+    }
+
+    /// 重みに従って操作の添字を選ぶ
+    /// 操作が1つの場合は乱数を消費しない
+    pub fn choose<R: Rng>(&self, rng: &mut R) -> usize {
+        if self.weights.len() == 1 {
+            return 0;
+        }
+        let total: f32 = self.weights.iter().sum();
+        let mut r = rng.gen_range(0.0..total);
+        for (i, w) in self.weights.iter().enumerate() {
+            if r < *w {
+                return i;
+            }
+            r -= w;
+        }
+        self.weights.len() - 1
+    }
+
+    /// 選んだ操作の結果を記録する
+    pub fn record(&mut self, i: usize, accepted: bool, improved: bool, best: bool) {
+        self.stats[i].used += 1;
+        if accepted {
+            self.stats[i].accepted += 1;
+        }
+        if improved {
+            self.stats[i].improved += 1;
+        }
+
+        if !self.adaptive {
+            return;
+        }
+
+        self.segment_uses[i] += 1;
+        self.segment_rewards[i] += if best {
+            REWARD_BEST
+        } else if improved {
+            REWARD_IMPROVED
+        } else if accepted {
+            REWARD_ACCEPTED
+        } else {
+            0.0
+        };
+        self.segment_step += 1;
+        if SEGMENT_LENGTH <= self.segment_step {
+            self.update_weights();
+        }
+    }
+
+    /// 区間の報酬から重みを更新する
+    fn update_weights(&mut self) {
+        for i in 0..self.weights.len() {
+            if 0 < self.segment_uses[i] {
+                let reward = self.segment_rewards[i] / self.segment_uses[i] as f32;
+                self.weights[i] = (1.0 - REACTION) * self.weights[i] + REACTION * reward;
+            }
+            self.weights[i] = self.weights[i].max(MIN_WEIGHT);
+            self.segment_rewards[i] = 0.0;
+            self.segment_uses[i] = 0;
+        }
+        self.segment_step = 0;
+    }
+
+    /// 操作ごとの統計を返す
+    pub fn stats(&self) -> Vec<OperatorStats> {
+        let total: f32 = self.weights.iter().sum();
+        self.stats
+            .iter()
+            .zip(&self.weights)
+            .map(|(s, w)| OperatorStats {
+                probability: w / total,
+                ..s.clone()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_single_operator() {
+        let mut selector = OperatorSelector::new(vec![1.0], true);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..300 {
+            let i = selector.choose(&mut rng);
+            selector.record(i, true, false, false);
+        }
+        let stats = selector.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].used, 300);
+        assert_eq!(stats[0].accepted, 300);
+        assert_eq!(stats[0].probability, 1.0);
+    }
+
+    #[test]
+    fn test_adaptive_weights() {
+        let mut selector = OperatorSelector::new(vec![0.5, 0.5], true);
+        for _ in 0..1000 {
+            selector.record(0, true, true, false);
+            selector.record(1, false, false, false);
+        }
+        let stats = selector.stats();
+        assert!(stats[1].probability < stats[0].probability);
+        assert_eq!(stats[0].improved, 1000);
+        assert_eq!(stats[1].accepted, 0);
+
+        let mut fixed = OperatorSelector::new(vec![0.5, 0.5], false);
+        for _ in 0..1000 {
+            fixed.record(0, true, true, false);
+            fixed.record(1, false, false, false);
+        }
+        assert_eq!(fixed.stats()[0].probability, 0.5);
+    }
+}
@@ -0,0 +1,116 @@
+//! 探索の経過を記録する型を提供
+
+/// 探索のステップ数、受理した回数、改善した回数と、一定ステップごとの最良のスコアを記録する
+#[derive(Debug, Clone)]
+pub struct RunStats<S> {
+    interval: u32,
+    steps: u32,
+    accepted: u32,
+    improved: u32,
+    trace: Vec<S>,
+}
+
+impl<S: Copy> RunStats<S> {
+    /// コンストラクタ
+    /// 最良のスコアを記録する間隔と、初期のスコアを指定する
+    /// 間隔が0の場合は1とする
+    pub fn new(interval: u32, initial_score: S) -> Self {
+        RunStats {
+            interval: interval.max(1),
+            steps: 0,
+            accepted: 0,
+            improved: 0,
+            trace: vec![initial_score],
+        }
+    }
+
+    /// 1ステップの結果を記録する
+    /// 移動を受理したか、移動でスコアが改善したかと、そのステップ後の最良のスコアを指定する
+    pub fn record(&mut self, accepted: bool, improved: bool, best_score: S) {
+        self.steps += 1;
+        if accepted {
+            self.accepted += 1;
+        }
+        if improved {
+            self.improved += 1;
+        }
+        if self.steps.is_multiple_of(self.interval) {
+            self.trace.push(best_score);
+        }
+    }
+
+    /// 探索の終了時に呼び、最後の最良のスコアを記録する
+    /// ステップ数がintervalの倍数でない場合のみ加えるため、最後のステップも推移に含まれる
+    pub fn finish(&mut self, best_score: S) {
+        if !self.steps.is_multiple_of(self.interval) {
+            self.trace.push(best_score);
+        }
+    }
+
+    /// 最良のスコアを記録する間隔
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// 実行したステップ数
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// 移動を受理したステップ数
+    pub fn accepted(&self) -> u32 {
+        self.accepted
+    }
+
+    /// 受理した移動でスコアが改善したステップ数
+    pub fn improved(&self) -> u32 {
+        self.improved
+    }
+
+    /// 初期と、intervalステップごとの最良のスコア
+    /// finishの後は、ステップ数がintervalの倍数でなくても最後のステップ後の最良のスコアで終わる
+    pub fn trace(&self) -> &[S] {
+        &self.trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut stats = RunStats::new(2, 10.0);
+        stats.record(true, true, 8.0);
+        stats.record(false, false, 8.0);
+        stats.record(true, false, 8.0);
+        stats.record(true, true, 5.0);
+        stats.record(false, false, 5.0);
+        assert_eq!(stats.steps(), 5);
+        assert_eq!(stats.accepted(), 3);
+        assert_eq!(stats.improved(), 2);
+        assert_eq!(stats.trace(), &[10.0, 8.0, 5.0]);
+    }
+
+    /// ステップ数が間隔の倍数でない場合のみ、最後の最良のスコアが加わるケース
+    #[test]
+    fn test_finish() {
+        let mut stats = RunStats::new(2, 10.0);
+        for best in [8.0, 7.0, 5.0, 4.0, 3.0] {
+            stats.record(true, true, best);
+        }
+        stats.finish(3.0);
+        assert_eq!(stats.trace(), &[10.0, 7.0, 4.0, 3.0]);
+
+        let mut stats = RunStats::new(2, 10.0);
+        for best in [8.0, 7.0] {
+            stats.record(true, true, best);
+        }
+        stats.finish(7.0);
+        assert_eq!(stats.trace(), &[10.0, 7.0]);
+
+        let mut stats = RunStats::new(2, 10.0);
+        stats.finish(10.0);
+        assert_eq!(stats.trace(), &[10.0]);
+    }
+}
@@ -0,0 +1,171 @@
+use super::{OperatorSelector, RunStats};
+
+use rand::Rng;
+
+/// タブーサーチの実行
+///
+/// 各ステップでselectorが選んだ更新関数で候補をcandidate_count個作り、
+/// タブーでない候補のうち最もスコアの良いものへ、悪化する場合でも移動する。
+/// diffは移動前後のモデルから変更された要素を返し、移動で変更された要素はtenureステップの間タブーとなる。
+/// ただし、最良のスコアを更新する候補はタブーであっても選ぶ。
+/// 各ステップで移動したかと最良のスコアはstatsに記録する。
+///
+/// # 例
+///
+/// ```
+/// use rand::Rng;
+///
+/// fn updatef<R: Rng>(x: &i32, rng: &mut R) -> i32 {
+///     x + rng.gen_range(-1..=1)
+/// }
+///
+/// fn evalf(x: &i32) -> f32 {
+///     ((x - 5) * (x - 5)) as f32
+/// }
+///
+/// let best_score: f32;
+/// let best_model: i32;
+///
+/// (best_score, best_model) = kinmu_annealing::run_tabu(
+///     25.0,
+///     &0,
+///     100,
+///     &mut [updatef],
+///     &mut kinmu_annealing::OperatorSelector::new(vec![1.0], false),
+///     &mut kinmu_annealing::RunStats::new(10, 25.0),
+///     evalf,
+///     |x: &i32, y: &i32| if x == y { vec![] } else { vec![*y] },
+///     3,
+///     10,
+///     &mut rand::thread_rng(),
+/// );
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn run_tabu<M, S, K, U, E, D, R>(
+    initial_score: S,
+    initial_model: &M,
+    step_count: u32,
+    updates: &mut [U],
+    selector: &mut OperatorSelector,
+    stats: &mut RunStats<S>,
+    mut eval: E,
+    mut diff: D,
+    tenure: u32,
+    candidate_count: u32,
+    rng: &mut R,
+) -> (S, M)
+where
+    M: Clone,
+    S: std::cmp::PartialOrd + Copy,
+    K: PartialEq,
+    U: FnMut(&M, &mut R) -> M,
+    E: FnMut(&M) -> S,
+    D: FnMut(&M, &M) -> Vec<K>,
+    R: Rng,
+{
+    let mut best_model = initial_model.clone();
+    let mut best_score = initial_score;
+
+    let mut current_model = initial_model.clone();
+    let mut current_score = initial_score;
+
+    // タブーな要素と、タブーが解除されるステップ
+    let mut tabu_list: Vec<(K, u32)> = Vec::new();
+
+    for loop_value in 0..step_count {
+        tabu_list.retain(|(_, until)| loop_value < *until);
+
+        let mut candidates = Vec::new();
+        // 選んだ候補の添字とスコア
+        let mut chosen: Option<(usize, S)> = None;
+        for _ in 0..candidate_count {
+            let i = selector.choose(rng);
+            let next_model = updates[i](&current_model, rng);
+            let keys = diff(&current_model, &next_model);
+            if keys.is_empty() {
+                selector.record(i, false, false, false);
+                continue;
+            }
+            let next_score = eval(&next_model);
+            let is_tabu = keys.iter().any(|k| tabu_list.iter().any(|(t, _)| t == k));
+            if (!is_tabu || next_score < best_score) && chosen.is_none_or(|(_, s)| next_score < s) {
+                chosen = Some((candidates.len(), next_score));
+            }
+            candidates.push((i, next_model, keys, next_score));
+        }
+
+        let mut moved = false;
+        let mut moved_improved = false;
+        for (c, (i, next_model, keys, next_score)) in candidates.into_iter().enumerate() {
+            if chosen.map(|(c, _)| c) != Some(c) {
+                selector.record(i, false, next_score < current_score, false);
+                continue;
+            }
+            let improved = next_score < current_score;
+            moved = true;
+            moved_improved = improved;
+            let best = next_score < best_score;
+            if next_score <= best_score {
+                best_model = next_model.clone();
+                best_score = next_score;
+            }
+            current_model = next_model;
+            current_score = next_score;
+            for k in keys {
+                tabu_list.push((k, loop_value + 1 + tenure));
+            }
+            selector.record(i, true, improved, best);
+        }
+        stats.record(moved, moved_improved, best_score);
+    }
+    stats.finish(best_score);
+
+    (best_score, best_model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    /// 最良の状態を通り過ぎても、最良のモデルを返すケース
+    #[test]
+    fn test_keep_best() {
+        // 各要素を反転する操作を順に試す
+        let mut update = |m: &Vec<bool>, rng: &mut rand::rngs::StdRng| {
+            let mut m = m.clone();
+            let i = rng.gen_range(0..m.len());
+            m[i] = !m[i];
+            m
+        };
+        // trueの数が1の状態が最良で、それ以外は悪化する
+        let eval = |m: &Vec<bool>| (m.iter().filter(|x| **x).count() as f32 - 1.0).abs();
+        let diff = |a: &Vec<bool>, b: &Vec<bool>| {
+            (0..a.len()).filter(|&i| a[i] != b[i]).collect::<Vec<_>>()
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut selector = OperatorSelector::new(vec![1.0], false);
+        let initial = vec![false; 4];
+        let mut stats = RunStats::new(5, eval(&initial));
+        let (score, model) = run_tabu(
+            eval(&initial),
+            &initial,
+            20,
+            std::slice::from_mut(&mut update),
+            &mut selector,
+            &mut stats,
+            eval,
+            diff,
+            2,
+            8,
+            &mut rng,
+        );
+        assert_eq!(score, 0.0);
+        assert_eq!(model.iter().filter(|x| **x).count(), 1);
+        assert_eq!(selector.stats()[0].accepted, 20);
+        assert_eq!(stats.accepted(), 20);
+        assert_eq!(stats.trace().len(), 5);
+        assert_eq!(stats.trace().last(), Some(&0.0));
+    }
+}
@@ -0,0 +1,54 @@
+//! 焼きなましの温度を、ランダムな更新によるスコアの変化量から決める関数を提供
+//!
+//! いずれもスコアが悪化する更新の変化量(正の値)を受け取り、
+//! 悪化する更新を目標の受理率で受理する温度を返す。
+//! 悪化する更新がない場合は0を返す。
+
+/// 正の値のみを取り出す
+fn positive_deltas(deltas: &[f32]) -> Vec<f32> {
+    deltas.iter().copied().filter(|d| *d > 0.0).collect()
+}
+
+/// basic_prob_funcの温度
+/// 悪化幅の平均をdとして、exp(-d / temp) = rateとなる温度を返す
+pub fn metropolis_temperature(deltas: &[f32], rate: f32) -> f32 {
+    let positive = positive_deltas(deltas);
+    if positive.is_empty() {
+        return 0.0;
+    }
+    let mean = positive.iter().sum::<f32>() / positive.len() as f32;
+    mean / -rate.ln()
+}
+
+/// threshold_prob_funcの温度
+/// 悪化幅のうち、割合rateが閾値以下になる値を返す
+pub fn threshold_temperature(deltas: &[f32], rate: f32) -> f32 {
+    let mut positive = positive_deltas(deltas);
+    if positive.is_empty() {
+        return 0.0;
+    }
+    positive.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let i = ((positive.len() as f32 * rate).ceil() as usize).clamp(1, positive.len());
+    positive[i - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metropolis_temperature() {
+        let temp = metropolis_temperature(&[-5.0, 0.0, 10.0, 30.0], 0.5);
+        assert!(((-20.0 / temp).exp() - 0.5).abs() < 1e-5);
+        assert_eq!(metropolis_temperature(&[-5.0, 0.0], 0.5), 0.0);
+    }
+
+    #[test]
+    fn test_threshold_temperature() {
+        let deltas = [4.0, -1.0, 1.0, 3.0, 2.0];
+        assert_eq!(threshold_temperature(&deltas, 0.5), 2.0);
+        assert_eq!(threshold_temperature(&deltas, 0.99), 4.0);
+        assert_eq!(threshold_temperature(&deltas, 0.01), 1.0);
+        assert_eq!(threshold_temperature(&[], 0.5), 0.0);
+    }
+}
@@ -16,7 +16,7 @@ pub trait Output<A> {
 }
 
 /// 入力、生成、出力を実行
-pub fn run<C, A, I: Input<C>, G: Generator<C, A>, O: Output<A>>(
+pub fn run<C, A, I: Input<C>, G: Generator<C, A> + ?Sized, O: Output<A>>(
     input: &mut I,
     generator: &mut G,
     output: &mut O,
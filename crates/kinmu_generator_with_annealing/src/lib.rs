@@ -1,19 +1,32 @@
 //! 焼きなまし法を用いた生成器を提供
 //! また、ここで要求するtraitを定義
 
+mod polish;
+mod recombination;
 mod seed;
 
 use kinmu_core::Generator;
 use kinmu_model::{
-    eval_scores_mut, AnnealingConfig, Answer, FillConfig, MainConfig, Schedule, ScheduleConfig,
-    ScoreProp,
+    diagnose_result, eval_scores_immut, eval_scores_mut, lower_bound_scores, Acceptance, Algorithm,
+    AnnealingConfig, Answer, ChildStats, FillConfig, MainConfig, ModelId, RecombinationConfig,
+    RecombinationFailure, RecombinationResult, Schedule, ScheduleConfig, Score, ScoreProp,
+    StageStats, StageType, ThreadFailure, ThreadStats, UpdateStats,
 };
 
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
 
+/// 温度を自動で決める際に試す更新の回数
+const TEMPERATURE_SAMPLE_COUNT: usize = 200;
+
+/// 段階ごとに記録する最良のスコアのおおよその数
+const TRACE_POINT_COUNT: u32 = 100;
+
 /// 焼きなまし法を用いた生成器
 /// 初めに表を埋めるための型Fと更新のための型Uを保持
 #[derive(Debug)]
@@ -37,7 +50,7 @@ impl<SP, S, SS, DS, F, U> Generator<MainConfig<SP, S, SS, DS>, Vec<Answer<SP, S,
     for GeneratorWithAnnealing<F, U>
 where
     SP: Clone + std::marker::Send + 'static + ScoreProp<S, SS, DS>,
-    S: Clone + std::marker::Send + 'static,
+    S: Clone + PartialEq + std::marker::Send + 'static,
     SS: Clone + std::marker::Send + 'static,
     DS: Clone + std::marker::Send + 'static,
     F: Fill<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
@@ -59,7 +72,7 @@ fn generate_schedules<SP, S, SS, DS, F, U>(
 ) -> anyhow::Result<Vec<Answer<SP, S, SS, DS>>>
 where
     SP: Clone + std::marker::Send + 'static + ScoreProp<S, SS, DS>,
-    S: Clone + std::marker::Send + 'static,
+    S: Clone + PartialEq + std::marker::Send + 'static,
     SS: Clone + std::marker::Send + 'static,
     DS: Clone + std::marker::Send + 'static,
     F: Fill<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
@@ -89,7 +102,7 @@ fn generate_schedule<SP, S, SS, DS, F, U>(
 ) -> anyhow::Result<Answer<SP, S, SS, DS>>
 where
     SP: Clone + std::marker::Send + 'static + ScoreProp<S, SS, DS>,
-    S: Clone + std::marker::Send + 'static,
+    S: Clone + PartialEq + std::marker::Send + 'static,
     SS: Clone + std::marker::Send + 'static,
     DS: Clone + std::marker::Send + 'static,
     F: Fill<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
@@ -97,7 +110,7 @@ where
 {
     let start = Instant::now();
 
-    let mut hs: Vec<thread::JoinHandle<anyhow::Result<_>>> = vec![];
+    let mut hs: Vec<thread::JoinHandle<Result<_, StageError>>> = vec![];
     for _ in 0..thread_count {
         let schedule_config = schedule_config.clone();
         let annealing_configs = schedule_config.annealing_configs.clone();
@@ -115,52 +128,527 @@ where
         }))
     }
 
+    // 失敗したスレッドがあっても、他のスレッドの結果は返す
     let mut models = Vec::new();
-    for h in hs.into_iter() {
-        models.push(h.join().unwrap()?);
+    let mut thread_stats = Vec::new();
+    let mut failures = Vec::new();
+    let mut logs = diagnose_result(schedule_config);
+    let mut run_stats = Vec::new();
+    for (t, result) in join_threads(hs).into_iter().enumerate() {
+        match result {
+            Ok(result) => {
+                let stages = result.stages;
+                models.push((t + 1, stages.model));
+                thread_stats.push(stages.update_stats);
+                run_stats.push(ThreadStats {
+                    thread: t + 1,
+                    fill_score: result.fill_score,
+                    fill_time: result.fill_time,
+                    stages: stages.stage_stats,
+                });
+                logs.extend(
+                    stages
+                        .logs
+                        .into_iter()
+                        .map(|log| format!("thread {}: {}", t + 1, log)),
+                );
+            }
+            Err(e) => failures.push(ThreadFailure {
+                schedule_config: schedule_config.name.clone(),
+                thread: t + 1,
+                stage: e.stage,
+                message: e.message,
+            }),
+        }
     }
 
+    // スレッドごとの結果はそのまま残し、組み換えの結果は別に記録する
+    let recombination = match &schedule_config.recombination {
+        Some(rc) if 2 <= models.len() => {
+            Some(recombine(schedule_config, rc, &models, update, &mut logs))
+        }
+        _ => None,
+    };
+
     Ok(Answer {
         models,
         schedule_config: schedule_config.clone(),
         total_time: start.elapsed(),
+        update_stats: merge_update_stats(thread_stats),
+        failures,
+        logs,
+        run_stats,
+        recombination,
     })
 }
 
+/// スレッドの終了を待ち、結果を返す
+/// パニックしたスレッドは段階threadのエラーとする
+fn join_threads<T>(
+    hs: Vec<thread::JoinHandle<Result<T, StageError>>>,
+) -> Vec<Result<T, StageError>> {
+    hs.into_iter()
+        .map(|h| {
+            h.join().unwrap_or_else(|e| {
+                Err(StageError {
+                    stage: String::from("thread"),
+                    message: format!("panicked: {}", panic_message(e.as_ref())),
+                })
+            })
+        })
+        .collect()
+}
+
+/// スレッドごとの表を交叉し、さらに焼きなましを行うことを指定した世代数繰り返す
+/// 各世代で表と同じ数の子を作り、親と子のうちスコアの良いものを次の世代に残す
+/// 最終世代の表はresultのスコアの良い順に並び、スレッドか世代と子の番号で区別する
+/// 子のログは世代と子の番号をつけてlogsに追加する
+fn recombine<SP, S, SS, DS, U>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    rc: &RecombinationConfig<SP>,
+    models: &[(usize, Schedule<S>)],
+    update: &U,
+    logs: &mut Vec<String>,
+) -> RecombinationResult<S>
+where
+    SP: Clone + std::marker::Send + 'static + ScoreProp<S, SS, DS>,
+    S: Clone + PartialEq + std::marker::Send + 'static,
+    SS: Clone + std::marker::Send + 'static,
+    DS: Clone + std::marker::Send + 'static,
+    U: Update<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
+{
+    let eval = |model: &Schedule<S>| result_score(schedule_config, model);
+
+    let population_size = models.len();
+    let mut population = models
+        .iter()
+        .map(|(t, m)| (eval(m), ModelId::Thread(*t), m.clone()))
+        .collect::<Vec<_>>();
+    population.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut children_update_stats = Vec::new();
+    let mut failures = Vec::new();
+    let mut run_stats = Vec::new();
+    let mut rng = seed::gen_rng_from_seed(rc.seed);
+    for generation in 1..=rc.generations {
+        let mut hs: Vec<thread::JoinHandle<Result<_, StageError>>> = vec![];
+        for _ in 0..population_size {
+            let (a, b) = recombination::choose_parents(population_size, &mut rng);
+            let child = recombination::crossover(
+                &population[a].2,
+                &population[b].2,
+                rc.crossover,
+                schedule_config.day.buffer_count,
+                &mut rng,
+            );
+            let schedule_config = schedule_config.clone();
+            let annealing_configs = rc.annealing_configs.clone();
+            let update = update.clone();
+            hs.push(thread::spawn(move || {
+                run_stages(
+                    &schedule_config,
+                    child,
+                    annealing_configs,
+                    &update,
+                    "recombination ",
+                )
+            }));
+        }
+        for (c, result) in join_threads(hs).into_iter().enumerate() {
+            let id = ModelId::Child {
+                generation,
+                child: c + 1,
+            };
+            match result {
+                Ok(result) => {
+                    logs.extend(
+                        result
+                            .logs
+                            .into_iter()
+                            .map(|log| format!("{}: {}", id, log)),
+                    );
+                    children_update_stats.push(result.update_stats);
+                    run_stats.push(ChildStats {
+                        id,
+                        stages: result.stage_stats,
+                    });
+                    population.push((eval(&result.model), id, result.model));
+                }
+                Err(e) => failures.push(RecombinationFailure {
+                    id,
+                    stage: e.stage,
+                    message: e.message,
+                }),
+            }
+        }
+        population.sort_by(|a, b| a.0.total_cmp(&b.0));
+        population.truncate(population_size);
+    }
+
+    RecombinationResult {
+        models: population.into_iter().map(|(_, id, m)| (id, m)).collect(),
+        update_stats: merge_update_stats(children_update_stats),
+        failures,
+        run_stats,
+    }
+}
+
+/// スレッド内の段階で起きたエラー
+#[derive(Debug)]
+struct StageError {
+    stage: String,
+    message: String,
+}
+
+/// 段階を実行し、エラーやパニックをStageErrorに変換する
+fn run_stage<T>(stage: &str, f: impl FnOnce() -> anyhow::Result<T>) -> Result<T, StageError> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(t)) => Ok(t),
+        Ok(Err(e)) => Err(StageError {
+            stage: stage.to_string(),
+            message: format!("{:?}", e),
+        }),
+        Err(e) => Err(StageError {
+            stage: stage.to_string(),
+            message: format!("panicked: {}", panic_message(e.as_ref())),
+        }),
+    }
+}
+
+/// パニックの内容を文字列にする
+fn panic_message(e: &(dyn Any + Send)) -> String {
+    if let Some(s) = e.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = e.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        String::from("unknown panic")
+    }
+}
+
+/// スレッドごとの更新関数の統計をまとめる
+/// 回数は合計し、選択確率は平均する
+fn merge_update_stats(thread_stats: Vec<Vec<Vec<UpdateStats>>>) -> Vec<Vec<UpdateStats>> {
+    let thread_count = thread_stats.len() as f32;
+    let mut merged: Vec<Vec<UpdateStats>> = Vec::new();
+    for stats in thread_stats {
+        if merged.is_empty() {
+            merged = stats
+                .iter()
+                .map(|stage| {
+                    stage
+                        .iter()
+                        .map(|s| UpdateStats {
+                            used: 0,
+                            accepted: 0,
+                            improved: 0,
+                            probability: 0.0,
+                            ..s.clone()
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+        for (merged_stage, stage) in merged.iter_mut().zip(stats) {
+            for (m, s) in merged_stage.iter_mut().zip(stage) {
+                m.used += s.used;
+                m.accepted += s.accepted;
+                m.improved += s.improved;
+                m.probability += s.probability / thread_count;
+            }
+        }
+    }
+    merged
+}
+
+/// 焼きなましの段階を順に実行した結果
+struct StagesResult<S> {
+    model: Schedule<S>,
+    /// 段階ごとの更新関数の統計
+    /// adaptive_updateでない段階の統計は空になる
+    update_stats: Vec<Vec<UpdateStats>>,
+    /// 段階ごとのステップ数や最良のスコアの推移
+    stage_stats: Vec<StageStats>,
+    /// 出力の冒頭に表示する、段階の名前から始まるログ
+    logs: Vec<String>,
+}
+
+/// 1つのスレッドで表を埋め、焼きなましを行った結果
+struct ThreadResult<S> {
+    /// 埋めた直後の表のresultのスコア
+    fill_score: Score,
+    fill_time: Duration,
+    stages: StagesResult<S>,
+}
+
 /// 焼きなましを実行する
+/// 失敗した場合、失敗した段階を返す
 fn annealing<SP, S, SS, DS, F, U>(
     schedule_config: ScheduleConfig<SP, S, SS, DS>,
     fill_config: FillConfig,
     annealing_configs: Vec<AnnealingConfig<SP>>,
     fill: F,
     update: U,
-) -> anyhow::Result<Schedule<S>>
+) -> Result<ThreadResult<S>, StageError>
 where
     SP: ScoreProp<S, SS, DS>,
-    S: Clone,
+    S: Clone + PartialEq,
     F: Fill<SP, S, SS, DS>,
     U: Update<SP, S, SS, DS>,
 {
-    let mut model = fill.run(
-        &fill_config.name,
-        &schedule_config,
-        &mut seed::gen_rng_from_seed(fill_config.seed),
-    )?;
-
-    let mut score;
-    for mut ac in annealing_configs {
-        let mut rng = seed::gen_rng_from_seed(ac.seed);
-        score = eval_scores_mut(
-            &mut ac.score_props,
-            &schedule_config.staff,
-            &schedule_config.day,
-            &model,
+    let start = Instant::now();
+    let model = run_stage("fill", || {
+        fill.run(
+            &fill_config.name,
+            &schedule_config,
+            &mut seed::gen_rng_from_seed(fill_config.seed),
+        )
+    })?;
+    let fill_time = start.elapsed();
+
+    Ok(ThreadResult {
+        fill_score: result_score(&schedule_config, &model),
+        fill_time,
+        stages: run_stages(&schedule_config, model, annealing_configs, &update, "")?,
+    })
+}
+
+/// 表のresultのスコアを計算する
+/// 段階によってscore_functionsが異なるため、段階の前後の比較にはこれを用いる
+fn result_score<SP, S, SS, DS>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    model: &Schedule<S>,
+) -> Score
+where
+    SP: ScoreProp<S, SS, DS>,
+{
+    schedule_config
+        .result
+        .score_functions
+        .iter()
+        .map(|sf| {
+            eval_scores_immut(
+                &sf.scores,
+                &schedule_config.staff,
+                &schedule_config.day,
+                model,
+            )
+        })
+        .sum()
+}
+
+/// 表に焼きなましの段階を順に実行する
+/// 段階の名前はprefixに続けてannealing 1などとする
+fn run_stages<SP, S, SS, DS, U>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    mut model: Schedule<S>,
+    annealing_configs: Vec<AnnealingConfig<SP>>,
+    update: &U,
+    prefix: &str,
+) -> Result<StagesResult<S>, StageError>
+where
+    SP: ScoreProp<S, SS, DS>,
+    S: Clone + PartialEq,
+    U: Update<SP, S, SS, DS>,
+{
+    let mut update_stats = Vec::new();
+    let mut stage_stats = Vec::new();
+    let mut logs = Vec::new();
+    for (i, mut ac) in annealing_configs.into_iter().enumerate() {
+        let stage = format!("{}annealing {}", prefix, i + 1);
+        let start = Instant::now();
+        let (next_model, stats, run_stats) = run_stage(&stage, || {
+            if let Some(log) = calibrate_temperature(schedule_config, &mut ac, &model, update)? {
+                logs.push(format!("{}: {}", stage, log));
+            }
+            annealing_stage(schedule_config, &mut ac, &model, update)
+        })?;
+        model = next_model;
+        update_stats.push(stats);
+        stage_stats.push(StageStats {
+            name: stage,
+            score: result_score(schedule_config, &model),
+            steps: run_stats.steps(),
+            accepted: run_stats.accepted(),
+            improved: run_stats.improved(),
+            trace_interval: run_stats.interval(),
+            best_score_trace: run_stats.trace().to_vec(),
+            time: start.elapsed(),
+        });
+    }
+
+    Ok(StagesResult {
+        model,
+        update_stats,
+        stage_stats,
+        logs,
+    })
+}
+
+/// temperatureが自動の場合、表にランダムな更新を試してスコアの変化量を測り、
+/// 悪化する更新の受理率が目標の値になるよう最初と最後の温度を決める
+/// 大洪水法では、最初の水位を現在のスコアに悪化幅を加えた値、最後の水位をスコアの下限とする
+/// 下限を見積もれない場合、最後の水位は現在のスコアとする
+/// 温度を用いない段階や、温度を指定した段階では何もしない
+/// スコアが悪化する更新が1つもなかった場合はエラーを返す
+/// 決めた温度をログとして返す
+fn calibrate_temperature<SP, S, SS, DS, U>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    ac: &mut AnnealingConfig<SP>,
+    model: &Schedule<S>,
+    update: &U,
+) -> anyhow::Result<Option<String>>
+where
+    SP: ScoreProp<S, SS, DS>,
+    U: Update<SP, S, SS, DS>,
+{
+    let auto = match ac.auto_temperature {
+        Some(auto)
+            if ac.stage_type == StageType::Annealing
+                && ac.algorithm == Algorithm::Annealing
+                && !matches!(ac.acceptance, Acceptance::LateAcceptance { .. }) =>
+        {
+            auto
+        }
+        _ => return Ok(None),
+    };
+
+    let mut rng = seed::gen_rng_from_seed(ac.seed);
+    let mut operators = update.generate_operators(&ac.update_func, schedule_config)?;
+    let dist = WeightedIndex::new(operators.iter().map(|(_, w, _)| *w))?;
+    let score = eval_scores_mut(
+        &mut ac.score_props,
+        &schedule_config.staff,
+        &schedule_config.day,
+        model,
+    );
+    let deltas = (0..TEMPERATURE_SAMPLE_COUNT)
+        .map(|_| {
+            let next = (operators[dist.sample(&mut rng)].2)(model, &mut rng);
+            eval_scores_mut(
+                &mut ac.score_props,
+                &schedule_config.staff,
+                &schedule_config.day,
+                &next,
+            ) - score
+        })
+        .collect::<Vec<_>>();
+    // 悪化幅が分からないと温度が0になり、メトロポリス基準の確率がNaNになる
+    anyhow::ensure!(
+        deltas.iter().any(|d| *d > 0.0),
+        "{}回のランダムな更新でスコアが悪化しなかったため、温度を自動で決められません\nヒント: temperatureを{{begin = 10, end = 0}}のような表で指定してください",
+        TEMPERATURE_SAMPLE_COUNT
+    );
+
+    let (begin, end) = match ac.acceptance {
+        Acceptance::Threshold => (
+            kinmu_annealing::threshold_temperature(&deltas, auto.initial_acceptance),
+            kinmu_annealing::threshold_temperature(&deltas, auto.final_acceptance),
+        ),
+        Acceptance::GreatDeluge => {
+            let lower_bound = lower_bound_scores(
+                &ac.score_props,
+                &schedule_config.staff,
+                &schedule_config.day,
+            );
+            (
+                score + kinmu_annealing::threshold_temperature(&deltas, auto.initial_acceptance),
+                // 下限を見積もれない場合は、現在のスコアまで下げる
+                if lower_bound.is_finite() {
+                    lower_bound
+                } else {
+                    score
+                },
+            )
+        }
+        _ => (
+            kinmu_annealing::metropolis_temperature(&deltas, auto.initial_acceptance),
+            kinmu_annealing::metropolis_temperature(&deltas, auto.final_acceptance),
+        ),
+    };
+    // basic_temp_funcは最初のステップでmin_tempとなるため、メトロポリス基準では最初の温度をmin_tempとする
+    if ac.acceptance == Acceptance::Metropolis {
+        ac.max_temp = end;
+        ac.min_temp = begin;
+    } else {
+        ac.max_temp = begin;
+        ac.min_temp = end;
+    }
+
+    Ok(Some(format!(
+        "auto temperature: begin = {}, end = {}",
+        begin, end
+    )))
+}
+
+/// 焼きなましの1つの段階を実行する
+/// algorithmに応じて焼きなまし法、タブーサーチ、山登り法のいずれかを用いる
+/// 段階後の表と、更新関数の統計、探索の経過を返す
+#[allow(clippy::type_complexity)]
+fn annealing_stage<SP, S, SS, DS, U>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    ac: &mut AnnealingConfig<SP>,
+    model: &Schedule<S>,
+    update: &U,
+) -> anyhow::Result<(
+    Schedule<S>,
+    Vec<UpdateStats>,
+    kinmu_annealing::RunStats<Score>,
+)>
+where
+    SP: ScoreProp<S, SS, DS>,
+    S: Clone + PartialEq,
+    U: Update<SP, S, SS, DS>,
+{
+    if ac.stage_type == StageType::Polish {
+        return polish_stage(schedule_config, ac, model, update);
+    }
+    let mut rng = seed::gen_rng_from_seed(ac.seed);
+    let score = eval_scores_mut(
+        &mut ac.score_props,
+        &schedule_config.staff,
+        &schedule_config.day,
+        model,
+    );
+    let prob_func = generate_prob_func(ac, score);
+    let temp_func = generate_temp_func(ac);
+    let mut run_stats = kinmu_annealing::RunStats::new(ac.step.div_ceil(TRACE_POINT_COUNT), score);
+    if let Algorithm::Tabu {
+        tenure,
+        candidate_count,
+    } = ac.algorithm
+    {
+        let (names, mut funcs, mut selector) = generate_selector(update, ac, schedule_config)?;
+        let (_, model) = kinmu_annealing::run_tabu(
+            score,
+            model,
+            ac.step,
+            &mut funcs,
+            &mut selector,
+            &mut run_stats,
+            |m| {
+                eval_scores_mut(
+                    &mut ac.score_props,
+                    &schedule_config.staff,
+                    &schedule_config.day,
+                    m,
+                )
+            },
+            changed_cells,
+            tenure,
+            candidate_count,
+            &mut rng,
         );
-        (_, model) = kinmu_annealing::run(
+        Ok((model, collect_update_stats(ac, names, &selector), run_stats))
+    } else if ac.adaptive_update {
+        let (names, mut funcs, mut selector) = generate_selector(update, ac, schedule_config)?;
+        let (_, model) = kinmu_annealing::run_with_operators(
             score,
-            &model,
+            model,
             ac.step,
-            update.generate(&ac.update_func, &schedule_config)?,
+            &mut funcs,
+            &mut selector,
+            &mut run_stats,
             |m| {
                 eval_scores_mut(
                     &mut ac.score_props,
@@ -171,13 +659,174 @@ where
             },
             ac.max_temp,
             ac.min_temp,
-            kinmu_annealing::basic_temp_func,
-            kinmu_annealing::basic_prob_func,
+            temp_func,
+            prob_func,
             &mut rng,
         );
+        Ok((model, collect_update_stats(ac, names, &selector), run_stats))
+    } else {
+        let mut func = update.generate(&ac.update_func, schedule_config)?;
+        let (_, model) = kinmu_annealing::run_with_operators(
+            score,
+            model,
+            ac.step,
+            std::slice::from_mut(&mut func),
+            &mut kinmu_annealing::OperatorSelector::new(vec![1.0], false),
+            &mut run_stats,
+            |m| {
+                eval_scores_mut(
+                    &mut ac.score_props,
+                    &schedule_config.staff,
+                    &schedule_config.day,
+                    m,
+                )
+            },
+            ac.max_temp,
+            ac.min_temp,
+            temp_func,
+            prob_func,
+            &mut rng,
+        );
+        Ok((model, Vec::new(), run_stats))
+    }
+}
+
+/// acceptanceから温度関数を選ぶ
+/// 閾値受理法と大洪水法では、閾値や水位をmax_tempからmin_tempへ下げる
+fn generate_temp_func<SP>(ac: &AnnealingConfig<SP>) -> fn(f32, f32, u32, u32) -> f32 {
+    match ac.acceptance {
+        Acceptance::Threshold | Acceptance::GreatDeluge => kinmu_annealing::decreasing_temp_func,
+        _ => kinmu_annealing::basic_temp_func,
     }
+}
 
-    Ok(model)
+/// algorithmとacceptanceから、更新後の表を受理する確率関数を生成する
+/// 遅延受理山登り法の場合、初期のスコアで記録を埋める
+fn generate_prob_func<SP>(
+    ac: &AnnealingConfig<SP>,
+    initial_score: Score,
+) -> Box<dyn FnMut(Score, Score, f32) -> f32> {
+    if ac.algorithm == Algorithm::HillClimbing {
+        return Box::new(kinmu_annealing::hill_climbing_prob_func);
+    }
+    match ac.acceptance {
+        Acceptance::Metropolis => Box::new(kinmu_annealing::basic_prob_func),
+        Acceptance::Threshold => Box::new(kinmu_annealing::threshold_prob_func),
+        Acceptance::GreatDeluge => Box::new(kinmu_annealing::great_deluge_prob_func),
+        Acceptance::LateAcceptance { length } => {
+            let mut la = kinmu_annealing::LateAcceptance::new(length as usize, initial_score);
+            Box::new(move |now, next, temp| la.prob_func(now, next, temp))
+        }
+    }
+}
+
+/// 更新関数の組とその名前、重みに従って選ぶOperatorSelectorを生成する
+#[allow(clippy::type_complexity)]
+fn generate_selector<'a, SP, S, SS, DS, U, R>(
+    update: &U,
+    ac: &AnnealingConfig<SP>,
+    schedule_config: &'a ScheduleConfig<SP, S, SS, DS>,
+) -> anyhow::Result<(
+    Vec<String>,
+    Vec<Box<dyn FnMut(&Schedule<S>, &mut R) -> Schedule<S> + 'a>>,
+    kinmu_annealing::OperatorSelector,
+)>
+where
+    U: Update<SP, S, SS, DS>,
+    R: Rng + 'a,
+{
+    let operators = update.generate_operators(&ac.update_func, schedule_config)?;
+    let mut names = Vec::new();
+    let mut weights = Vec::new();
+    let mut funcs = Vec::new();
+    for (name, weight, func) in operators {
+        names.push(name);
+        weights.push(weight);
+        funcs.push(func);
+    }
+    let selector = kinmu_annealing::OperatorSelector::new(weights, ac.adaptive_update);
+    Ok((names, funcs, selector))
+}
+
+/// OperatorSelectorの統計を更新関数の名前と組にする
+/// adaptive_updateでない場合は空になる
+fn collect_update_stats<SP>(
+    ac: &AnnealingConfig<SP>,
+    names: Vec<String>,
+    selector: &kinmu_annealing::OperatorSelector,
+) -> Vec<UpdateStats> {
+    if !ac.adaptive_update {
+        return Vec::new();
+    }
+    names
+        .into_iter()
+        .zip(selector.stats())
+        .map(|(name, s)| UpdateStats {
+            name,
+            used: s.used,
+            accepted: s.accepted,
+            improved: s.improved,
+            probability: s.probability,
+        })
+        .collect()
+}
+
+/// 2つの表で異なる枠(職員, 日付)を列挙する
+/// タブーサーチで、最近変更した枠を記録するために用いる
+fn changed_cells<S: PartialEq>(a: &Schedule<S>, b: &Schedule<S>) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    for (staff, (row_a, row_b)) in a.iter().zip(b).enumerate() {
+        for (day, (x, y)) in row_a.iter().zip(row_b).enumerate() {
+            if x != y {
+                cells.push((staff, day));
+            }
+        }
+    }
+    cells
+}
+
+/// 局所探索の段階を実行する
+/// 更新関数の統計は空になる
+#[allow(clippy::type_complexity)]
+fn polish_stage<SP, S, SS, DS, U>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    ac: &mut AnnealingConfig<SP>,
+    model: &Schedule<S>,
+    update: &U,
+) -> anyhow::Result<(
+    Schedule<S>,
+    Vec<UpdateStats>,
+    kinmu_annealing::RunStats<Score>,
+)>
+where
+    SP: ScoreProp<S, SS, DS>,
+    S: Clone,
+    U: Update<SP, S, SS, DS>,
+{
+    let neighborhood = update.generate_neighborhood(&ac.update_func, schedule_config)?;
+    let score = eval_scores_mut(
+        &mut ac.score_props,
+        &schedule_config.staff,
+        &schedule_config.day,
+        model,
+    );
+    let mut run_stats = kinmu_annealing::RunStats::new(ac.step.div_ceil(TRACE_POINT_COUNT), score);
+    let (_, model) = polish::run(
+        score,
+        model,
+        ac.step,
+        neighborhood,
+        |m| {
+            eval_scores_mut(
+                &mut ac.score_props,
+                &schedule_config.staff,
+                &schedule_config.day,
+                m,
+            )
+        },
+        &mut run_stats,
+    );
+    Ok((model, Vec::new(), run_stats))
 }
 
 /// GeneratorWithAnnealingで用いるFillの共通のふるまい
@@ -195,9 +844,284 @@ pub trait Fill<SP, S, SS, DS> {
 #[allow(clippy::type_complexity)]
 pub trait Update<SP, S, SS, DS> {
     /// 名前とScheduleConfigからクロージャーを生成
-    fn generate<'a, R: Rng>(
+    fn generate<'a, R: Rng + 'a>(
         &self,
         name: &str,
         schedule_config: &'a ScheduleConfig<SP, S, SS, DS>,
     ) -> anyhow::Result<Box<dyn FnMut(&Schedule<S>, &mut R) -> Schedule<S> + 'a>>;
+
+    /// 名前とScheduleConfigから、名前と重みのついたクロージャーの組を生成
+    /// 更新関数の選択確率を適応的に変更する場合に用いる
+    /// 既定では、名前の更新関数1つを重み1で返す
+    fn generate_operators<'a, R: Rng + 'a>(
+        &self,
+        name: &str,
+        schedule_config: &'a ScheduleConfig<SP, S, SS, DS>,
+    ) -> anyhow::Result<
+        Vec<(
+            String,
+            f32,
+            Box<dyn FnMut(&Schedule<S>, &mut R) -> Schedule<S> + 'a>,
+        )>,
+    > {
+        Ok(vec![(
+            name.to_string(),
+            1.0,
+            self.generate(name, schedule_config)?,
+        )])
+    }
+
+    /// 名前とScheduleConfigから、局所探索で用いる近傍を列挙するクロージャーを生成
+    /// 近傍の1つは、変更する枠(職員, 日付)と変更後のシフトの組の列で表す
+    /// 既定では、近傍を列挙できないとしてエラーを返す
+    fn generate_neighborhood<'a>(
+        &self,
+        name: &str,
+        _schedule_config: &'a ScheduleConfig<SP, S, SS, DS>,
+    ) -> anyhow::Result<Box<dyn Fn(&Schedule<S>) -> Vec<Vec<(usize, usize, S)>> + 'a>> {
+        Err(anyhow::anyhow!("更新関数{}の近傍は列挙できません", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use kinmu_model::{DayConfig, StaffConfig};
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// 何もしないScoreProp
+    #[derive(Clone, Debug, Default)]
+    struct ZeroScore;
+
+    impl ScoreProp<u8, (), ()> for ZeroScore {
+        fn eval_mut(
+            &mut self,
+            staff_config: &StaffConfig,
+            day_config: &DayConfig<u8, (), ()>,
+            schedule: &Schedule<u8>,
+        ) -> Score {
+            self.eval_immut(staff_config, day_config, schedule)
+        }
+
+        fn eval_immut(
+            &self,
+            _staff_config: &StaffConfig,
+            _day_config: &DayConfig<u8, (), ()>,
+            _schedule: &Schedule<u8>,
+        ) -> Score {
+            0.0
+        }
+    }
+
+    /// 2回目に呼ばれたときにパニックするFill
+    /// 呼ばれた回数は、スレッドごとに複製されたFillの間で共有する
+    #[derive(Clone, Debug, Default)]
+    struct PanicOnSecondFill {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Fill<ZeroScore, u8, (), ()> for PanicOnSecondFill {
+        fn run<R: Rng>(
+            &self,
+            _name: &str,
+            _schedule_config: &ScheduleConfig<ZeroScore, u8, (), ()>,
+            _rng: &mut R,
+        ) -> anyhow::Result<Schedule<u8>> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 1 {
+                panic!("<fill>");
+            }
+            Ok(vec![vec![0]])
+        }
+    }
+
+    /// 表を変更しないUpdate
+    #[derive(Clone, Debug)]
+    struct NoUpdate;
+
+    impl Update<ZeroScore, u8, (), ()> for NoUpdate {
+        fn generate<'a, R: Rng + 'a>(
+            &self,
+            _name: &str,
+            _schedule_config: &'a ScheduleConfig<ZeroScore, u8, (), ()>,
+        ) -> anyhow::Result<Box<dyn FnMut(&Schedule<u8>, &mut R) -> Schedule<u8> + 'a>> {
+            Ok(Box::new(|schedule, _| schedule.clone()))
+        }
+    }
+
+    /// 表の値の合計をスコアとするScoreProp
+    #[derive(Clone, Debug, Default)]
+    struct SumScore;
+
+    impl ScoreProp<u8, (), ()> for SumScore {
+        fn eval_mut(
+            &mut self,
+            staff_config: &StaffConfig,
+            day_config: &DayConfig<u8, (), ()>,
+            schedule: &Schedule<u8>,
+        ) -> Score {
+            self.eval_immut(staff_config, day_config, schedule)
+        }
+
+        fn eval_immut(
+            &self,
+            _staff_config: &StaffConfig,
+            _day_config: &DayConfig<u8, (), ()>,
+            schedule: &Schedule<u8>,
+        ) -> Score {
+            schedule.iter().flatten().map(|x| *x as Score).sum()
+        }
+    }
+
+    /// 最初の枠を1増やすUpdate
+    #[derive(Clone, Debug)]
+    struct Increment;
+
+    impl Update<SumScore, u8, (), ()> for Increment {
+        fn generate<'a, R: Rng + 'a>(
+            &self,
+            _name: &str,
+            _schedule_config: &'a ScheduleConfig<SumScore, u8, (), ()>,
+        ) -> anyhow::Result<Box<dyn FnMut(&Schedule<u8>, &mut R) -> Schedule<u8> + 'a>> {
+            Ok(Box::new(|schedule, _| {
+                let mut next = schedule.clone();
+                next[0][0] += 1;
+                next
+            }))
+        }
+    }
+
+    /// 悪化幅が常に1の場合、目標の受理率になる温度に決まるケース
+    /// 悪化する更新がない場合はエラーになる
+    #[test]
+    fn test_calibrate_temperature() {
+        let schedule_config = ScheduleConfig::<SumScore, u8, (), ()>::default();
+        let auto = kinmu_model::AutoTemperature {
+            initial_acceptance: 0.5,
+            final_acceptance: 0.01,
+        };
+        let mut ac = AnnealingConfig {
+            score_props: vec![SumScore],
+            auto_temperature: Some(auto),
+            ..Default::default()
+        };
+        let log =
+            calibrate_temperature(&schedule_config, &mut ac, &vec![vec![0]], &Increment).unwrap();
+        assert!(log.is_some());
+        // 最初のステップの温度がmin_tempになる
+        assert!(((-1.0 / ac.min_temp).exp() - 0.5).abs() < 1e-5);
+        assert!(((-1.0 / ac.max_temp).exp() - 0.01).abs() < 1e-5);
+
+        ac.acceptance = Acceptance::Threshold;
+        calibrate_temperature(&schedule_config, &mut ac, &vec![vec![0]], &Increment).unwrap();
+        assert_eq!((ac.max_temp, ac.min_temp), (1.0, 1.0));
+
+        // 温度を指定した場合は何もしない
+        ac.auto_temperature = None;
+        assert!(
+            calibrate_temperature(&schedule_config, &mut ac, &vec![vec![0]], &Increment)
+                .unwrap()
+                .is_none()
+        );
+
+        let schedule_config = ScheduleConfig::<ZeroScore, u8, (), ()>::default();
+        let mut ac = AnnealingConfig {
+            score_props: vec![ZeroScore],
+            auto_temperature: Some(auto),
+            ..Default::default()
+        };
+        assert!(
+            calibrate_temperature(&schedule_config, &mut ac, &vec![vec![0]], &NoUpdate).is_err()
+        );
+    }
+
+    /// スレッドの1つがパニックしても、残りの表がそのスレッド番号で返るケース
+    #[test]
+    fn test_thread_numbering_with_failure() {
+        let schedule_config = ScheduleConfig::<ZeroScore, u8, (), ()> {
+            name: String::from("test.toml"),
+            ..Default::default()
+        };
+        let ans = generate_schedule(
+            &schedule_config,
+            3,
+            &PanicOnSecondFill::default(),
+            &NoUpdate,
+        )
+        .unwrap();
+
+        assert_eq!(ans.failures.len(), 1);
+        let failure = &ans.failures[0];
+        assert_eq!(failure.stage, "fill");
+        assert_eq!(failure.message, "panicked: <fill>");
+
+        let mut threads = ans.models.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        assert_eq!(
+            threads,
+            ans.run_stats.iter().map(|s| s.thread).collect::<Vec<_>>()
+        );
+        assert!(!threads.contains(&failure.thread));
+        threads.push(failure.thread);
+        threads.sort();
+        assert_eq!(threads, vec![1, 2, 3]);
+    }
+
+    /// 組み換えの子が世代と子の番号で記録され、スレッド番号と衝突しないケース
+    /// スコアがすべて等しいため、最終世代には元のスレッドの表が残る
+    /// 近傍を列挙できない段階では、すべての子が失敗する
+    #[test]
+    fn test_recombine() {
+        let schedule_config = ScheduleConfig::<ZeroScore, u8, (), ()>::default();
+        let models = vec![(1, vec![vec![0, 0]]), (3, vec![vec![1, 1]])];
+        let mut rc = RecombinationConfig {
+            generations: 2,
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        let mut logs = Vec::new();
+        let result = recombine(&schedule_config, &rc, &models, &NoUpdate, &mut logs);
+        assert_eq!(
+            result.models,
+            vec![
+                (ModelId::Thread(1), vec![vec![0, 0]]),
+                (ModelId::Thread(3), vec![vec![1, 1]]),
+            ]
+        );
+        assert!(result.failures.is_empty());
+        assert_eq!(
+            result.run_stats.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![
+                ModelId::Child {
+                    generation: 1,
+                    child: 1
+                },
+                ModelId::Child {
+                    generation: 1,
+                    child: 2
+                },
+                ModelId::Child {
+                    generation: 2,
+                    child: 1
+                },
+                ModelId::Child {
+                    generation: 2,
+                    child: 2
+                },
+            ]
+        );
+
+        rc.annealing_configs = vec![AnnealingConfig {
+            stage_type: StageType::Polish,
+            ..Default::default()
+        }];
+        let result = recombine(&schedule_config, &rc, &models, &NoUpdate, &mut logs);
+        assert_eq!(result.models.len(), 2);
+        assert!(result.run_stats.is_empty());
+        assert_eq!(result.failures.len(), 4);
+        assert_eq!(result.failures[3].stage, "recombination annealing 1");
+        assert_eq!(result.failures[3].id.to_string(), "generation 2 child 2");
+    }
 }
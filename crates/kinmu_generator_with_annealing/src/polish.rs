@@ -0,0 +1,100 @@
+//! 最急降下法による局所探索を行うモジュール
+
+use kinmu_annealing::RunStats;
+use kinmu_model::{Schedule, Score};
+
+/// 近傍の1つを表に適用し、変更前のシフトを返す
+fn apply<S: Clone>(model: &mut Schedule<S>, changes: &[(usize, usize, S)]) -> Vec<S> {
+    changes
+        .iter()
+        .map(|(staff, day, shift)| std::mem::replace(&mut model[*staff][*day], shift.clone()))
+        .collect()
+}
+
+/// applyで適用した変更を元に戻す
+/// 同じ枠が複数回変更されている場合に備え、逆順に戻す
+fn revert<S>(model: &mut Schedule<S>, changes: &[(usize, usize, S)], olds: Vec<S>) {
+    for ((staff, day, _), old) in changes.iter().zip(olds).rev() {
+        model[*staff][*day] = old;
+    }
+}
+
+/// 近傍のうち最もスコアの良いものへの移動を、改善できなくなるか最大ステップ数に達するまで繰り返す
+/// 移動したステップはstatsに記録し、終了時に最後のスコアを記録する
+/// 最終的なスコアと表を返す
+#[allow(clippy::type_complexity)]
+pub fn run<S, N, E>(
+    initial_score: Score,
+    initial_model: &Schedule<S>,
+    max_step: u32,
+    neighborhood: N,
+    mut eval: E,
+    stats: &mut RunStats<Score>,
+) -> (Score, Schedule<S>)
+where
+    S: Clone,
+    N: Fn(&Schedule<S>) -> Vec<Vec<(usize, usize, S)>>,
+    E: FnMut(&Schedule<S>) -> Score,
+{
+    let mut score = initial_score;
+    let mut model = initial_model.clone();
+    for _ in 0..max_step {
+        let mut best: Option<(Score, Vec<(usize, usize, S)>)> = None;
+        for changes in neighborhood(&model) {
+            let olds = apply(&mut model, &changes);
+            let s = eval(&model);
+            revert(&mut model, &changes, olds);
+            if s < best.as_ref().map_or(score, |(b, _)| *b) {
+                best = Some((s, changes));
+            }
+        }
+        match best {
+            Some((s, changes)) => {
+                apply(&mut model, &changes);
+                score = s;
+                stats.record(true, true, score);
+            }
+            None => break,
+        }
+    }
+    stats.finish(score);
+    (score, model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1つの枠を0か1に変える近傍で、1の数を減らすケース
+    #[test]
+    fn test_descend_to_local_optimum() {
+        let model = vec![vec![1, 1, 0], vec![0, 1, 1]];
+        let neighborhood = |m: &Schedule<i32>| {
+            let mut ns = Vec::new();
+            for (r, row) in m.iter().enumerate() {
+                for (c, x) in row.iter().enumerate() {
+                    ns.push(vec![(r, c, 1 - x)]);
+                }
+            }
+            ns
+        };
+        let eval = |m: &Schedule<i32>| m.iter().flatten().sum::<i32>() as Score;
+
+        let mut stats = RunStats::new(1, eval(&model));
+        let (score, result) = run(eval(&model), &model, 100, neighborhood, eval, &mut stats);
+        assert_eq!(0.0, score);
+        assert_eq!(4, stats.steps());
+        assert_eq!(&[4.0, 3.0, 2.0, 1.0, 0.0], stats.trace());
+        assert_eq!(vec![vec![0, 0, 0], vec![0, 0, 0]], result);
+
+        let mut stats = RunStats::new(1, eval(&model));
+        let (score, result) = run(eval(&model), &model, 2, neighborhood, eval, &mut stats);
+        assert_eq!(2.0, score);
+        assert_eq!(2, result.iter().flatten().filter(|x| **x == 1).count());
+
+        // 移動の回数が間隔の倍数でなくても、最後のスコアが記録される
+        let mut stats = RunStats::new(3, eval(&model));
+        run(eval(&model), &model, 100, neighborhood, eval, &mut stats);
+        assert_eq!(&[4.0, 1.0, 0.0], stats.trace());
+    }
+}
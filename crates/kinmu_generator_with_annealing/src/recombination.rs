@@ -0,0 +1,106 @@
+//! スレッドごとの結果を組み換えるための交叉と親の選択を行うモジュール
+//!
+//! 交叉では枠ごとにどちらかの親のシフトをそのまま用いるため、
+//! 希望された表で固定された枠や、枠ごとに入れられるシフトの制限は保たれる
+
+use kinmu_model::{Crossover, Schedule};
+
+use rand::Rng;
+
+/// 交叉のWeekで用いる区切りの日数
+const WEEK_LENGTH: usize = 7;
+
+/// 2つの親から子を作る
+/// buffer_countまでの日付は親aのものを用いる
+pub fn crossover<S: Clone, R: Rng>(
+    a: &Schedule<S>,
+    b: &Schedule<S>,
+    crossover: Crossover,
+    buffer_count: usize,
+    rng: &mut R,
+) -> Schedule<S> {
+    let mut child = a.clone();
+    match crossover {
+        Crossover::Staff => {
+            for (row, b_row) in child.iter_mut().zip(b) {
+                if rng.gen::<bool>() {
+                    row.clone_from(b_row);
+                }
+            }
+        }
+        Crossover::Week => {
+            let day_count = a.first().map_or(0, |row| row.len());
+            let mut begin = buffer_count;
+            while begin < day_count {
+                let end = (begin + WEEK_LENGTH).min(day_count);
+                if rng.gen::<bool>() {
+                    for (row, b_row) in child.iter_mut().zip(b) {
+                        row[begin..end].clone_from_slice(&b_row[begin..end]);
+                    }
+                }
+                begin = end;
+            }
+        }
+    }
+    child
+}
+
+/// スコアの良い順に並んだ個体からトーナメントで異なる2つの親を選ぶ
+/// 個体は2つ以上必要
+pub fn choose_parents<R: Rng>(population_size: usize, rng: &mut R) -> (usize, usize) {
+    let a = tournament(population_size, rng);
+    loop {
+        let b = tournament(population_size, rng);
+        if a != b {
+            return (a, b);
+        }
+    }
+}
+
+/// ランダムに選んだ2つの個体のうち、スコアの良いほうを選ぶ
+fn tournament<R: Rng>(population_size: usize, rng: &mut R) -> usize {
+    let x = rng.gen_range(0..population_size);
+    let y = rng.gen_range(0..population_size);
+    x.min(y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    /// 職員ごとの交叉では行ごとに、週ごとの交叉では区切りごとにどちらかの親と一致するケース
+    #[test]
+    fn test_crossover() {
+        let a = vec![vec![0; 17]; 3];
+        let b = vec![vec![1; 17]; 3];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+        for _ in 0..10 {
+            let child = crossover(&a, &b, Crossover::Staff, 3, &mut rng);
+            for row in &child {
+                assert!(row == &a[0] || row == &b[0]);
+            }
+
+            let child = crossover(&a, &b, Crossover::Week, 3, &mut rng);
+            for row in &child {
+                assert_eq!(row, &child[0]);
+                assert_eq!(&row[..3], &[0, 0, 0]);
+                for block in row[3..].chunks(WEEK_LENGTH) {
+                    assert!(block.iter().all(|x| *x == block[0]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_choose_parents() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (a, b) = choose_parents(2, &mut rng);
+            assert_ne!(a, b);
+            assert!(a < 2 && b < 2);
+        }
+    }
+}
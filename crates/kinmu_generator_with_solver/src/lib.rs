@@ -0,0 +1,391 @@
+//! 制約ソルバーを用いた生成器を提供
+//! また、ここで要求するtraitを定義
+//!
+//! ソルバーで扱えるスコアは制約として厳密に満たし、
+//! 扱えないスコアがある場合は、ソルバーの解を初期値として焼きなましを行う
+//! 制約を満たす解が見つからない場合は、通常の焼きなましで生成する
+//! ソルバーのみで生成する場合も、thread_countの数だけ解を探す
+
+mod solver;
+
+pub use solver::{Cell, Constraint, SolveResult};
+
+use kinmu_core::Generator;
+use kinmu_generator_with_annealing::{Fill, GeneratorWithAnnealing, Update};
+use kinmu_model::{
+    diagnose_result, Answer, MainConfig, Schedule, ScheduleConfig, ScoreProp, ThreadFailure,
+};
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// 制約ソルバーを用いた生成器
+/// ソルバーの制限時間と、焼きなましで用いるFの型とUの型を保持
+#[derive(Debug)]
+pub struct GeneratorWithSolver<F, U> {
+    fill: F,
+    update: U,
+    time_limit: Duration,
+}
+
+impl<F, U> GeneratorWithSolver<F, U> {
+    /// コンストラクタ
+    /// 焼きなましで用いるFill, Updateと、勤務表ごとのソルバーの制限時間を要求
+    pub fn new(fill: F, update: U, time_limit: Duration) -> Self {
+        GeneratorWithSolver {
+            fill,
+            update,
+            time_limit,
+        }
+    }
+}
+
+/// 生成器の実装
+/// SPにはToConstraintsを、SSにはCandidatesを要求
+/// 焼きなましを行うため、GeneratorWithAnnealingと同じ制約も要求
+impl<SP, S, SS, DS, F, U> Generator<MainConfig<SP, S, SS, DS>, Vec<Answer<SP, S, SS, DS>>>
+    for GeneratorWithSolver<F, U>
+where
+    SP: Clone + std::marker::Send + 'static + ScoreProp<S, SS, DS> + ToConstraints<SP, S, SS, DS>,
+    SP: fmt::Display,
+    S: Clone + PartialEq + std::marker::Send + 'static,
+    SS: Clone + std::marker::Send + 'static + Candidates<S>,
+    DS: Clone + std::marker::Send + 'static,
+    F: Fill<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
+    U: Update<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
+{
+    fn run(
+        &mut self,
+        config: &MainConfig<SP, S, SS, DS>,
+    ) -> anyhow::Result<Vec<Answer<SP, S, SS, DS>>> {
+        let mut answers = Vec::new();
+        for schedule_config in &config.schedule_configs {
+            answers.push(self.generate_schedule(schedule_config, config.thread_count)?);
+        }
+        Ok(answers)
+    }
+}
+
+impl<F, U> GeneratorWithSolver<F, U> {
+    /// 1つの勤務表をソルバーで生成し、必要なら焼きなましを行う
+    fn generate_schedule<SP, S, SS, DS>(
+        &self,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+        thread_count: Option<u32>,
+    ) -> anyhow::Result<Answer<SP, S, SS, DS>>
+    where
+        SP: Clone
+            + std::marker::Send
+            + 'static
+            + ScoreProp<S, SS, DS>
+            + ToConstraints<SP, S, SS, DS>
+            + fmt::Display,
+        S: Clone + PartialEq + std::marker::Send + 'static,
+        SS: Clone + std::marker::Send + 'static + Candidates<S>,
+        DS: Clone + std::marker::Send + 'static,
+        F: Fill<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
+        U: Update<SP, S, SS, DS> + Clone + std::marker::Send + 'static,
+    {
+        let start = Instant::now();
+        let mut logs = Vec::new();
+
+        let mut constraints = Vec::new();
+        let mut unsupported = Vec::new();
+        for sp in schedule_config
+            .result
+            .score_functions
+            .iter()
+            .flat_map(|sf| &sf.scores)
+        {
+            match sp.to_constraints(schedule_config) {
+                Some(cs) => constraints.extend(cs),
+                None => unsupported.push(sp.to_string()),
+            }
+        }
+
+        let candidates = make_candidates(schedule_config);
+        let mut rng: Box<dyn rand::RngCore> = match schedule_config.fill.seed {
+            Some(s) => Box::new(StdRng::seed_from_u64(s)),
+            None => Box::new(rand::thread_rng()),
+        };
+        let deadline = start + self.time_limit;
+        let result = solver::solve(&candidates, &constraints, deadline, &mut rng);
+
+        let schedule = match result {
+            SolveResult::Solved(schedule) => {
+                logs.push(format!(
+                    "solver: 制約を満たす解が見つかりました ({:?})",
+                    start.elapsed()
+                ));
+                if unsupported.is_empty() {
+                    logs.extend(diagnose_result(schedule_config));
+                    // 残りのスレッドの分も、同じ乱数で続けて解を探す
+                    let mut models = vec![(1, schedule)];
+                    let mut failures = Vec::new();
+                    for t in 2..=thread_count.unwrap_or(1) as usize {
+                        match solver::solve(&candidates, &constraints, deadline, &mut rng) {
+                            SolveResult::Solved(schedule) => models.push((t, schedule)),
+                            _ => failures.push(ThreadFailure {
+                                schedule_config: schedule_config.name.clone(),
+                                thread: t,
+                                stage: String::from("solver"),
+                                message: String::from("制限時間内に解が見つかりませんでした"),
+                            }),
+                        }
+                    }
+                    return Ok(Answer {
+                        models,
+                        schedule_config: schedule_config.clone(),
+                        total_time: start.elapsed(),
+                        update_stats: vec![Vec::new(); schedule_config.annealing_configs.len()],
+                        failures,
+                        logs,
+                        run_stats: Vec::new(),
+                        recombination: None,
+                    });
+                }
+                for name in &unsupported {
+                    logs.push(format!(
+                        "solver: 未対応のスコアのため焼きなましを行います: {}",
+                        name
+                    ));
+                }
+                Some(schedule)
+            }
+            SolveResult::Infeasible => {
+                logs.push(String::from(
+                    "solver: 制約を満たす解が存在しないため、焼きなましで生成します",
+                ));
+                None
+            }
+            SolveResult::Timeout => {
+                logs.push(String::from(
+                    "solver: 制限時間内に解が見つからなかったため、焼きなましで生成します",
+                ));
+                None
+            }
+        };
+
+        let main_config = MainConfig {
+            schedule_configs: vec![schedule_config.clone()],
+            thread_count,
+        };
+        let mut answers = match schedule {
+            Some(schedule) => {
+                GeneratorWithAnnealing::new(SolvedFill { schedule }, self.update.clone())
+                    .run(&main_config)?
+            }
+            None => GeneratorWithAnnealing::new(self.fill.clone(), self.update.clone())
+                .run(&main_config)?,
+        };
+        let mut answer = answers.remove(0);
+        logs.append(&mut answer.logs);
+        answer.logs = logs;
+        answer.total_time = start.elapsed();
+        Ok(answer)
+    }
+}
+
+/// 枠ごとのシフトの候補を作る
+fn make_candidates<SP, S, SS, DS>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+) -> Vec<Vec<Vec<S>>>
+where
+    SS: Candidates<S>,
+{
+    let day = &schedule_config.day;
+    (0..schedule_config.staff.count)
+        .map(|r| {
+            (0..day.count)
+                .map(|c| day.schedule_states[r][c].candidates(&day.requested_schedule[r][c]))
+                .collect()
+        })
+        .collect()
+}
+
+/// ソルバーの解をそのまま返すFill
+#[derive(Debug, Clone)]
+struct SolvedFill<S> {
+    schedule: Schedule<S>,
+}
+
+impl<SP, S: Clone, SS, DS> Fill<SP, S, SS, DS> for SolvedFill<S> {
+    fn run<R: Rng>(
+        &self,
+        _name: &str,
+        _schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+        _rng: &mut R,
+    ) -> anyhow::Result<Schedule<S>> {
+        Ok(self.schedule.clone())
+    }
+}
+
+/// GeneratorWithSolverで用いる、スコアを制約に変換するふるまい
+pub trait ToConstraints<SP, S, SS, DS> {
+    /// ソルバーで扱えるスコアなら、満たすべき制約に変換する
+    /// 扱えない場合はNoneを返す
+    fn to_constraints(
+        &self,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    ) -> Option<Vec<Constraint<S>>>;
+}
+
+/// GeneratorWithSolverで用いる、枠の状態から入れてよいシフトを返すふるまい
+pub trait Candidates<S> {
+    /// 枠の状態と希望された表のシフトから、入れてよいシフトの候補を返す
+    fn candidates(&self, requested: &S) -> Vec<S>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use kinmu_model::{DayConfig, Score, ScoreFunction, StaffConfig};
+
+    /// 日ごとにIがちょうど1人であることを求めるScoreProp
+    #[derive(Clone, Debug, Default)]
+    struct OneIPerDay;
+
+    impl fmt::Display for OneIPerDay {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "OneIPerDay")
+        }
+    }
+
+    impl ScoreProp<char, bool, ()> for OneIPerDay {
+        fn eval_mut(
+            &mut self,
+            staff_config: &StaffConfig,
+            day_config: &DayConfig<char, bool, ()>,
+            schedule: &Schedule<char>,
+        ) -> Score {
+            self.eval_immut(staff_config, day_config, schedule)
+        }
+
+        fn eval_immut(
+            &self,
+            _staff_config: &StaffConfig,
+            day_config: &DayConfig<char, bool, ()>,
+            schedule: &Schedule<char>,
+        ) -> Score {
+            (0..day_config.count)
+                .filter(|&c| schedule.iter().filter(|row| row[c] == 'I').count() != 1)
+                .count() as Score
+        }
+    }
+
+    impl ToConstraints<OneIPerDay, char, bool, ()> for OneIPerDay {
+        fn to_constraints(
+            &self,
+            schedule_config: &ScheduleConfig<OneIPerDay, char, bool, ()>,
+        ) -> Option<Vec<Constraint<char>>> {
+            Some(
+                (0..schedule_config.day.count)
+                    .map(|c| Constraint::Count {
+                        cells: (0..schedule_config.staff.count).map(|r| (r, c)).collect(),
+                        shift: 'I',
+                        min: 1,
+                        max: 1,
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    /// trueの枠は希望された表のシフトに固定する
+    impl Candidates<char> for bool {
+        fn candidates(&self, requested: &char) -> Vec<char> {
+            if *self {
+                vec![*requested]
+            } else {
+                vec!['I', 'N']
+            }
+        }
+    }
+
+    /// 希望された表をそのまま返すFill
+    #[derive(Clone, Debug)]
+    struct RequestedFill;
+
+    impl Fill<OneIPerDay, char, bool, ()> for RequestedFill {
+        fn run<R: Rng>(
+            &self,
+            _name: &str,
+            schedule_config: &ScheduleConfig<OneIPerDay, char, bool, ()>,
+            _rng: &mut R,
+        ) -> anyhow::Result<Schedule<char>> {
+            Ok(schedule_config.day.requested_schedule.clone())
+        }
+    }
+
+    /// 表を変更しないUpdate
+    #[derive(Clone, Debug)]
+    struct NoUpdate;
+
+    impl Update<OneIPerDay, char, bool, ()> for NoUpdate {
+        fn generate<'a, R: Rng + 'a>(
+            &self,
+            _name: &str,
+            _schedule_config: &'a ScheduleConfig<OneIPerDay, char, bool, ()>,
+        ) -> anyhow::Result<Box<dyn FnMut(&Schedule<char>, &mut R) -> Schedule<char> + 'a>>
+        {
+            Ok(Box::new(|schedule, _| schedule.clone()))
+        }
+    }
+
+    /// 3人3日で、固定する枠をstatesで指定したScheduleConfig
+    fn config(states: Vec<Vec<bool>>) -> ScheduleConfig<OneIPerDay, char, bool, ()> {
+        let mut schedule_config: ScheduleConfig<OneIPerDay, char, bool, ()> = Default::default();
+        schedule_config.staff.count = 3;
+        schedule_config.day.count = 3;
+        schedule_config.day.requested_schedule = vec![vec!['N'; 3]; 3];
+        schedule_config.day.schedule_states = states;
+        schedule_config.fill.seed = Some(0);
+        schedule_config.result.score_functions = vec![ScoreFunction {
+            scores: vec![OneIPerDay],
+            ..Default::default()
+        }];
+        schedule_config
+    }
+
+    /// ソルバーのみで生成する場合、thread_countの数だけ制約を満たす表を返すケース
+    #[test]
+    fn test_solved_thread_count() {
+        let schedule_config = config(vec![vec![false; 3]; 3]);
+        let ans = GeneratorWithSolver::new(RequestedFill, NoUpdate, Duration::from_secs(10))
+            .generate_schedule(&schedule_config, Some(3))
+            .unwrap();
+
+        assert!(ans.failures.is_empty());
+        assert_eq!(
+            ans.models.iter().map(|(t, _)| *t).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        for (_, model) in &ans.models {
+            assert_eq!(
+                OneIPerDay.eval_immut(&schedule_config.staff, &schedule_config.day, model),
+                0.0
+            );
+        }
+    }
+
+    /// 固定された枠のみで制約を満たさない場合、焼きなましで生成するケース
+    #[test]
+    fn test_fixed_infeasible() {
+        let mut states = vec![vec![false; 3]; 3];
+        for row in &mut states {
+            row[0] = true;
+        }
+        let schedule_config = config(states);
+        let ans = GeneratorWithSolver::new(RequestedFill, NoUpdate, Duration::from_secs(10))
+            .generate_schedule(&schedule_config, Some(2))
+            .unwrap();
+
+        assert!(ans.logs[0].contains("制約を満たす解が存在しない"));
+        assert_eq!(ans.models.len(), 2);
+        assert_eq!(ans.models[0].1, schedule_config.day.requested_schedule);
+    }
+}
@@ -0,0 +1,418 @@
+//! 枠ごとのシフトの候補と制約から、制約をすべて満たす表を探すソルバーのモジュール
+//!
+//! 枠ごとの候補をビット集合で持ち、制約伝播をしながら深さ優先探索を行う
+
+use kinmu_model::Schedule;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// 表の枠 (職員, 日)
+pub type Cell = (usize, usize);
+
+/// ソルバーで扱う制約
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint<S> {
+    /// 枠の集合のうち、シフトがshiftである枠の数がmin以上max以下
+    Count {
+        cells: Vec<Cell>,
+        shift: S,
+        min: usize,
+        max: usize,
+    },
+    /// 枠とシフトの組がすべて同時には成り立たない
+    Forbid { literals: Vec<(Cell, S)> },
+}
+
+/// ソルバーの結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveResult<S> {
+    /// 制約をすべて満たす表
+    Solved(Schedule<S>),
+    /// 制約をすべて満たす表が存在しない
+    Infeasible,
+    /// 制限時間内に見つからなかった
+    Timeout,
+}
+
+/// 枠の候補を表すビット集合
+type Domain = u64;
+
+/// 内部で用いる、シフトを添字に変換した制約
+enum IndexedConstraint {
+    Count {
+        cells: Vec<usize>,
+        bit: Domain,
+        min: usize,
+        max: usize,
+    },
+    Forbid {
+        literals: Vec<(usize, Domain)>,
+    },
+}
+
+/// 探索の状態
+struct Solver<'a, R> {
+    constraints: Vec<IndexedConstraint>,
+    watches: Vec<Vec<usize>>,
+    deadline: Instant,
+    rng: &'a mut R,
+}
+
+/// 探索の失敗の理由
+enum Failure {
+    Conflict,
+    Timeout,
+}
+
+/// 枠ごとのシフトの候補と制約から、制約をすべて満たす表を探す
+/// 候補に含まれるシフトの種類は64個まで
+pub fn solve<S, R>(
+    candidates: &[Vec<Vec<S>>],
+    constraints: &[Constraint<S>],
+    deadline: Instant,
+    rng: &mut R,
+) -> SolveResult<S>
+where
+    S: Clone + PartialEq,
+    R: Rng,
+{
+    // 出現するシフトを添字に対応させる
+    let mut shifts: Vec<S> = Vec::new();
+    for s in candidates.iter().flatten().flatten() {
+        if !shifts.contains(s) {
+            shifts.push(s.clone());
+        }
+    }
+    if 64 < shifts.len() {
+        return SolveResult::Infeasible;
+    }
+    let bit_of = |s: &S| -> Domain { shifts.iter().position(|x| x == s).map_or(0, |i| 1 << i) };
+
+    let width = candidates.first().map_or(0, |row| row.len());
+    let domains: Vec<Domain> = candidates
+        .iter()
+        .flatten()
+        .map(|ss| ss.iter().fold(0, |d, s| d | bit_of(s)))
+        .collect();
+
+    let index = |(r, c): &Cell| r * width + c;
+    // 候補が1つの枠のみからなる制約は、表によらず一定のため探索では扱わず、ここで一度だけ判定する
+    let is_constant = |c: &Constraint<S>| match c {
+        Constraint::Count { cells, .. } => cells
+            .iter()
+            .all(|cell| domains[index(cell)].count_ones() == 1),
+        Constraint::Forbid { literals } => literals
+            .iter()
+            .all(|(cell, _)| domains[index(cell)].count_ones() == 1),
+    };
+    let is_violated = |c: &Constraint<S>| match c {
+        Constraint::Count {
+            cells,
+            shift,
+            min,
+            max,
+        } => {
+            let count = cells
+                .iter()
+                .filter(|cell| domains[index(cell)] == bit_of(shift))
+                .count();
+            count < *min || *max < count
+        }
+        Constraint::Forbid { literals } => literals
+            .iter()
+            .all(|(cell, s)| domains[index(cell)] == bit_of(s)),
+    };
+    if constraints.iter().any(|c| is_constant(c) && is_violated(c)) {
+        return SolveResult::Infeasible;
+    }
+    let constraints: Vec<IndexedConstraint> = constraints
+        .iter()
+        .filter(|c| !is_constant(c))
+        .map(|c| match c {
+            Constraint::Count {
+                cells,
+                shift,
+                min,
+                max,
+            } => IndexedConstraint::Count {
+                cells: cells.iter().map(index).collect(),
+                bit: bit_of(shift),
+                min: *min,
+                max: *max,
+            },
+            Constraint::Forbid { literals } => IndexedConstraint::Forbid {
+                literals: literals
+                    .iter()
+                    .map(|(c, s)| (index(c), bit_of(s)))
+                    .collect(),
+            },
+        })
+        .collect();
+
+    let mut watches = vec![Vec::new(); domains.len()];
+    for (i, c) in constraints.iter().enumerate() {
+        match c {
+            IndexedConstraint::Count { cells, .. } => {
+                for &cell in cells {
+                    watches[cell].push(i);
+                }
+            }
+            IndexedConstraint::Forbid { literals } => {
+                for &(cell, _) in literals {
+                    watches[cell].push(i);
+                }
+            }
+        }
+    }
+
+    let mut solver = Solver {
+        constraints,
+        watches,
+        deadline,
+        rng,
+    };
+
+    match solver.run(domains) {
+        Ok(domains) => SolveResult::Solved(
+            domains
+                .chunks(width.max(1))
+                .map(|row| {
+                    row.iter()
+                        .map(|d| shifts[d.trailing_zeros() as usize].clone())
+                        .collect()
+                })
+                .collect(),
+        ),
+        Err(Failure::Conflict) => SolveResult::Infeasible,
+        Err(Failure::Timeout) => SolveResult::Timeout,
+    }
+}
+
+impl<R: Rng> Solver<'_, R> {
+    /// 初めにすべての制約を伝播してから探索する
+    fn run(&mut self, mut domains: Vec<Domain>) -> Result<Vec<Domain>, Failure> {
+        if domains.contains(&0) {
+            return Err(Failure::Conflict);
+        }
+        let all = (0..self.constraints.len()).collect();
+        self.propagate(&mut domains, all)?;
+        self.search(domains)
+    }
+
+    /// 候補が最も少ない未確定の枠を選び、候補を順に試す
+    fn search(&mut self, domains: Vec<Domain>) -> Result<Vec<Domain>, Failure> {
+        if self.deadline <= Instant::now() {
+            return Err(Failure::Timeout);
+        }
+
+        let mut best: Option<(u32, usize)> = None;
+        for (i, d) in domains.iter().enumerate() {
+            let n = d.count_ones();
+            if 1 < n && best.is_none_or(|(m, _)| n < m) {
+                best = Some((n, i));
+            }
+        }
+        let Some((_, cell)) = best else {
+            return Ok(domains);
+        };
+
+        let mut bits: Vec<Domain> = (0..64)
+            .map(|i| 1 << i)
+            .filter(|b| domains[cell] & b != 0)
+            .collect();
+        bits.shuffle(self.rng);
+
+        for bit in bits {
+            let mut next = domains.clone();
+            next[cell] = bit;
+            let queue = self.watches[cell].iter().copied().collect();
+            match self.propagate(&mut next, queue) {
+                Ok(()) => match self.search(next) {
+                    Ok(ans) => return Ok(ans),
+                    Err(Failure::Timeout) => return Err(Failure::Timeout),
+                    Err(Failure::Conflict) => {}
+                },
+                Err(Failure::Conflict) => {}
+                Err(Failure::Timeout) => return Err(Failure::Timeout),
+            }
+        }
+        Err(Failure::Conflict)
+    }
+
+    /// 制約を伝播し、候補を絞る
+    /// 候補が変化した枠に関する制約を、変化がなくなるまで繰り返し調べる
+    fn propagate(&self, domains: &mut [Domain], mut queue: VecDeque<usize>) -> Result<(), Failure> {
+        let mut changed = Vec::new();
+        while let Some(i) = queue.pop_front() {
+            changed.clear();
+            match &self.constraints[i] {
+                IndexedConstraint::Count {
+                    cells,
+                    bit,
+                    min,
+                    max,
+                } => {
+                    let fixed = cells.iter().filter(|&&c| domains[c] == *bit).count();
+                    let possible = cells.iter().filter(|&&c| domains[c] & bit != 0).count();
+                    if *max < fixed || possible < *min {
+                        return Err(Failure::Conflict);
+                    }
+                    if fixed == *max {
+                        // これ以上増やせないため、未確定の枠から取り除く
+                        for &c in cells {
+                            if domains[c] != *bit && domains[c] & bit != 0 {
+                                domains[c] &= !bit;
+                                changed.push(c);
+                            }
+                        }
+                    } else if possible == *min {
+                        // 候補に含む枠はすべてそのシフトにする必要がある
+                        for &c in cells {
+                            if domains[c] != *bit && domains[c] & bit != 0 {
+                                domains[c] = *bit;
+                                changed.push(c);
+                            }
+                        }
+                    }
+                }
+                IndexedConstraint::Forbid { literals } => {
+                    let mut undecided = None;
+                    let mut undecided_count = 0;
+                    let mut satisfied = false;
+                    for &(c, bit) in literals {
+                        if domains[c] & bit == 0 {
+                            satisfied = true;
+                            break;
+                        }
+                        if domains[c] != bit {
+                            undecided = Some((c, bit));
+                            undecided_count += 1;
+                        }
+                    }
+                    if !satisfied {
+                        match (undecided_count, undecided) {
+                            (0, _) => return Err(Failure::Conflict),
+                            (1, Some((c, bit))) => {
+                                domains[c] &= !bit;
+                                changed.push(c);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            for &c in &changed {
+                if domains[c] == 0 {
+                    return Err(Failure::Conflict);
+                }
+                for &j in &self.watches[c] {
+                    if j != i && !queue.contains(&j) {
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    fn deadline() -> Instant {
+        Instant::now() + Duration::from_secs(10)
+    }
+
+    #[test]
+    fn test_solve_count_and_forbid() {
+        // 3人3日で、毎日ちょうど1人がI、0番と1番は同じ日にIにならない
+        let candidates = vec![vec![vec!['I', 'N']; 3]; 3];
+        let mut constraints = Vec::new();
+        for c in 0..3 {
+            constraints.push(Constraint::Count {
+                cells: vec![(0, c), (1, c), (2, c)],
+                shift: 'I',
+                min: 1,
+                max: 1,
+            });
+            constraints.push(Constraint::Forbid {
+                literals: vec![((0, c), 'I'), ((1, c), 'I')],
+            });
+        }
+        // 2番は毎日Iにならない
+        constraints.push(Constraint::Count {
+            cells: vec![(2, 0), (2, 1), (2, 2)],
+            shift: 'I',
+            min: 0,
+            max: 0,
+        });
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+        match solve(&candidates, &constraints, deadline(), &mut rng) {
+            SolveResult::Solved(schedule) => {
+                for c in 0..3 {
+                    let count = schedule.iter().filter(|row| row[c] == 'I').count();
+                    assert_eq!(count, 1);
+                }
+                assert_eq!(schedule[2], vec!['N', 'N', 'N']);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_infeasible() {
+        // 固定された枠と矛盾する
+        let candidates = vec![vec![vec!['K']], vec![vec!['I', 'N']]];
+        let constraints = vec![Constraint::Count {
+            cells: vec![(0, 0), (1, 0)],
+            shift: 'I',
+            min: 2,
+            max: 2,
+        }];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+        assert_eq!(
+            solve(&candidates, &constraints, deadline(), &mut rng),
+            SolveResult::Infeasible
+        );
+    }
+
+    #[test]
+    fn test_constant_violated() {
+        // 候補が1つの枠のみからなる制約でも、満たさない場合は解なしとする
+        let candidates = vec![
+            vec![vec!['I'], vec!['I', 'N']],
+            vec![vec!['I'], vec!['I', 'N']],
+        ];
+        let constraints = vec![
+            Constraint::Count {
+                cells: vec![(0, 1), (1, 1)],
+                shift: 'I',
+                min: 1,
+                max: 1,
+            },
+            Constraint::Forbid {
+                literals: vec![((0, 0), 'I'), ((1, 0), 'I')],
+            },
+        ];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+        assert_eq!(
+            solve(&candidates, &constraints, deadline(), &mut rng),
+            SolveResult::Infeasible
+        );
+        assert!(matches!(
+            solve(&candidates, &constraints[..1], deadline(), &mut rng),
+            SolveResult::Solved(_)
+        ));
+    }
+}
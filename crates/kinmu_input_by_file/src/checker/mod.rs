@@ -3,24 +3,70 @@
 use anyhow::Context as _;
 use std::cmp::Ordering;
 
-use kinmu_model::{AnnealingConfig, MainConfig, ScheduleConfig, ScoreFunction};
+use kinmu_model::{
+    Acceptance, Algorithm, AnnealingConfig, MainConfig, ScheduleConfig, ScoreFunction, StageType,
+};
+
+/// 関数名の候補として提示する編集距離の上限
+const MAX_SUGGESTION_DISTANCE: usize = 3;
 
 /// 読み込み、変換したconfigが正常か判定する
-pub fn run<SP, S, SS, DS>(config: &MainConfig<SP, S, SS, DS>) -> anyhow::Result<()>
+/// fillとupdateの関数名とパラメータもここで判定する
+pub fn run<SP, S, SS, DS, F, U>(
+    config: &MainConfig<SP, S, SS, DS>,
+    fill: &F,
+    update: &U,
+) -> anyhow::Result<()>
 where
     SP: Check<SP, S, SS, DS>,
+    F: CheckFunction<SP, S, SS, DS>,
+    U: CheckFunction<SP, S, SS, DS>,
 {
     for schedule_config in &config.schedule_configs {
         check_schedule_config(schedule_config)
             .context("schedule_configの変換チェックに失敗しました")?;
+        fill.check_function(&schedule_config.fill.name, schedule_config)
+            .context("fill.functionの変換チェックに失敗しました")?;
         for annealing_config in &schedule_config.annealing_configs {
-            check_annealing_config(annealing_config, schedule_config)
-                .context("annealing_configの変換チェックに失敗しました")?;
+            check_annealing_config_with_update(annealing_config, schedule_config, update)?;
+        }
+        if let Some(recombination) = &schedule_config.recombination {
+            anyhow::ensure!(
+                2 <= config.thread_count.unwrap_or(1),
+                "annealing.recombinationを用いるにはthread_countを2以上にする必要があります"
+            );
+            for annealing_config in &recombination.annealing_configs {
+                check_annealing_config_with_update(annealing_config, schedule_config, update)
+                    .context("annealing.recombinationの変換チェックに失敗しました")?;
+            }
         }
     }
     Ok(())
 }
 
+/// annealing_configと、その段階で用いるupdate_functionのチェック
+fn check_annealing_config_with_update<SP, S, SS, DS, U>(
+    annealing_config: &AnnealingConfig<SP>,
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    update: &U,
+) -> anyhow::Result<()>
+where
+    SP: Check<SP, S, SS, DS>,
+    U: CheckFunction<SP, S, SS, DS>,
+{
+    check_annealing_config(annealing_config, schedule_config)
+        .context("annealing_configの変換チェックに失敗しました")?;
+    match annealing_config.stage_type {
+        StageType::Annealing => {
+            update.check_function(&annealing_config.update_func, schedule_config)
+        }
+        StageType::Polish => {
+            update.check_neighborhood_function(&annealing_config.update_func, schedule_config)
+        }
+    }
+    .context("annealing_configのupdate_functionの変換チェックに失敗しました")
+}
+
 /// schedule_configのチェック
 fn check_schedule_config<SP, S, SS, DS>(
     schedule_config: &ScheduleConfig<SP, S, SS, DS>,
@@ -35,6 +81,8 @@ where
 
     check_ng_list(schedule_config).context("staff.ng_listの変換チェックに失敗しました")?;
 
+    check_pair_list(schedule_config).context("staff.pair_listの変換チェックに失敗しました")?;
+
     check_day_states(schedule_config).context("day.statesの変換チェックに失敗しました")?;
 
     check_buffer(schedule_config).context("day.buffer_countの変換チェックに失敗しました")?;
@@ -47,6 +95,8 @@ where
 
     check_day_attributes(schedule_config).context("day.attributesの変換チェックに失敗しました")?;
 
+    check_wishes(schedule_config).context("day.wishesの変換チェックに失敗しました")?;
+
     check_score_functions(&schedule_config.result.score_functions, schedule_config)
         .context("result.score_functionsの変換チェックに失敗しました")?;
 
@@ -67,6 +117,43 @@ where
         .try_for_each(|x| x.check(schedule_config))
         .context("score_functionsの変換チェックに失敗しました")?;
 
+    if annealing_config.stage_type == StageType::Polish {
+        anyhow::ensure!(
+            annealing_config.algorithm == Algorithm::Annealing,
+            "stage_typeがpolishの段階ではalgorithmを指定できません"
+        );
+    }
+    if annealing_config.acceptance != Acceptance::Metropolis {
+        anyhow::ensure!(
+            annealing_config.stage_type == StageType::Annealing
+                && annealing_config.algorithm == Algorithm::Annealing,
+            "acceptanceはalgorithmがannealingの段階でのみ指定できます"
+        );
+    }
+    if let Acceptance::LateAcceptance { length } = annealing_config.acceptance {
+        anyhow::ensure!(
+            0 < length,
+            "late_acceptance_lengthは1以上である必要があります"
+        );
+    }
+    if let Some(auto) = annealing_config.auto_temperature {
+        anyhow::ensure!(
+            0.0 < auto.final_acceptance
+                && auto.final_acceptance <= auto.initial_acceptance
+                && auto.initial_acceptance < 1.0,
+            "temperatureの受理率は0 < final_acceptance <= initial_acceptance < 1を満たす必要があります"
+        );
+    }
+    if let Algorithm::Tabu {
+        candidate_count, ..
+    } = annealing_config.algorithm
+    {
+        anyhow::ensure!(
+            0 < candidate_count,
+            "tabu_candidate_countは1以上である必要があります"
+        );
+    }
+
     Ok(())
 }
 
@@ -145,6 +232,63 @@ fn check_ng_list<SP, S, SS, DS>(
     Ok(())
 }
 
+/// ペアリストが正常か
+fn check_pair_list<SP, S, SS, DS>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+) -> anyhow::Result<()> {
+    for (i, (from, to)) in schedule_config.staff.pair_list.iter().enumerate() {
+        if schedule_config.staff.count <= *from {
+            Err(anyhow::anyhow!(
+                "staff.pair_listの{}番目のfrom({})がstaffの番号の最大値({})より大きいです",
+                i + 1,
+                *from,
+                schedule_config.staff.count - 1
+            ))?;
+        }
+        if schedule_config.staff.count <= *to {
+            Err(anyhow::anyhow!(
+                "staff.pair_listの{}番目のto({})がstaffの番号の最大値({})より大きいです",
+                i + 1,
+                *to,
+                schedule_config.staff.count - 1
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// 希望のリストが正常か
+fn check_wishes<SP, S, SS, DS>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+) -> anyhow::Result<()> {
+    for (i, wish) in schedule_config.day.wishes.iter().enumerate() {
+        if schedule_config.staff.count <= wish.staff {
+            Err(anyhow::anyhow!(
+                "day.wishesの{}番目のstaff({})がstaffの番号の最大値({})より大きいです",
+                i + 1,
+                wish.staff,
+                schedule_config.staff.count - 1
+            ))?;
+        }
+        if schedule_config.day.count <= wish.day {
+            Err(anyhow::anyhow!(
+                "day.wishesの{}番目のday({})が日数({})より大きいです",
+                i + 1,
+                wish.day + 1 - schedule_config.day.buffer_count,
+                schedule_config.day.count - schedule_config.day.buffer_count
+            ))?;
+        }
+        if !wish.priority.is_finite() || wish.priority < 0.0 {
+            Err(anyhow::anyhow!(
+                "day.wishesの{}番目のpriority({})は0以上の有限な値である必要があります",
+                i + 1,
+                wish.priority
+            ))?;
+        }
+    }
+    Ok(())
+}
+
 /// DayStateが日数だけあるか
 fn check_day_states<SP, S, SS, DS>(
     schedule_config: &ScheduleConfig<SP, S, SS, DS>,
@@ -284,3 +428,104 @@ pub trait Check<SP, S, SS, DS>: Sized {
     /// 正常でない場合、anyhow::Result<()>をcontextをつけてErrで返す
     fn check(&self, schedule_config: &ScheduleConfig<SP, S, SS, DS>) -> anyhow::Result<()>;
 }
+
+/// fillやupdateの関数名とパラメータが正常か判定するトレイト
+pub trait CheckFunction<SP, S, SS, DS> {
+    /// schedule_configから、関数名nameとそのパラメータが正常か判定
+    /// 正常でない場合、anyhow::Result<()>をcontextをつけてErrで返す
+    fn check_function(
+        &self,
+        name: &str,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    ) -> anyhow::Result<()>;
+
+    /// 局所探索の段階で近傍を列挙する関数として、関数名nameとそのパラメータが正常か判定
+    /// 既定ではcheck_functionと同じ判定をする
+    fn check_neighborhood_function(
+        &self,
+        name: &str,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+    ) -> anyhow::Result<()> {
+        self.check_function(name, schedule_config)
+    }
+}
+
+/// 登録されていない関数名のエラーを作る
+/// 登録されている関数名に近いものがあればヒントとして示す
+pub fn unknown_function_error(name: &str, candidates: &[&str]) -> anyhow::Error {
+    match suggest_name(name, candidates) {
+        Some(candidate) => anyhow::anyhow!(
+            "関数{}は存在しません\nヒント: {}ではありませんか？",
+            name,
+            candidate
+        ),
+        None => anyhow::anyhow!(
+            "関数{}は存在しません\nヒント: 使用できる関数は{}です",
+            name,
+            candidates.join(", ")
+        ),
+    }
+}
+
+/// 候補の中から、名前に最も近いものを返す
+/// パラメータは無視し、編集距離が一定以下のもののみ返す
+fn suggest_name<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let base = name.trim().split(' ').next().unwrap_or("");
+    candidates
+        .iter()
+        .map(|c| (edit_distance(base, c), *c))
+        .filter(|(d, _)| *d <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+/// 2つの文字列の編集距離
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kinmu_model::{Score, Wish};
+
+    #[test]
+    fn suggest_name_test() {
+        let candidates = ["update_range", "update_noh_repeat", "update_moves"];
+        assert_eq!(
+            suggest_name("update_rnage", &candidates),
+            Some("update_range")
+        );
+        assert_eq!(
+            suggest_name("update_mvoes ([N], [(Change, 1)])", &candidates),
+            Some("update_moves")
+        );
+        assert_eq!(suggest_name("fill_noh", &candidates), None);
+    }
+
+    #[test]
+    fn check_wishes_priority_test() {
+        let mut schedule_config: ScheduleConfig<(), (), (), ()> = Default::default();
+        schedule_config.staff.count = 1;
+        schedule_config.day.count = 1;
+        for (priority, ok) in [(1.0, true), (0.0, true), (-1.0, false), (Score::NAN, false)] {
+            schedule_config.day.wishes = vec![Wish {
+                staff: 0,
+                day: 0,
+                shift: (),
+                priority,
+            }];
+            assert_eq!(check_wishes(&schedule_config).is_ok(), ok);
+        }
+    }
+}
@@ -1,12 +1,64 @@
 //! annealing_configを変換する関数を提供するモジュール
 
-use super::{super::reader::types::RawAnnealingConfig, FromConfig};
-use kinmu_model::AnnealingConfig;
+use super::{
+    super::reader::types::{
+        RawAcceptance, RawAlgorithm, RawAnnealingConfig, RawStageType, RawTemperature,
+        RawUpdateFunction,
+    },
+    FromConfig,
+};
+use kinmu_model::{Acceptance, Algorithm, AnnealingConfig, AutoTemperature, StageType};
+
+/// tabu_tenureを省略した場合の値
+const DEFAULT_TABU_TENURE: u32 = 10;
+
+/// tabu_candidate_countを省略した場合の値
+const DEFAULT_TABU_CANDIDATE_COUNT: u32 = 20;
+
+/// late_acceptance_lengthを省略した場合の値
+const DEFAULT_LATE_ACCEPTANCE_LENGTH: u32 = 100;
+
+/// temperatureを"auto"とした場合の、最初と最後の悪化する更新の受理率
+const DEFAULT_AUTO_TEMPERATURE: AutoTemperature = AutoTemperature {
+    initial_acceptance: 0.8,
+    final_acceptance: 0.001,
+};
 
 /// RawAnnealingConfigをAnnealingConfigに変換する
 pub fn convert_annealing_config<SP: FromConfig + Clone>(
     config: RawAnnealingConfig,
 ) -> anyhow::Result<AnnealingConfig<SP>> {
+    check_unused_keys(&config)?;
+
+    // 自動の場合、温度は焼きなましの直前に決める
+    // 温度を用いない段階では省略できる
+    let (max_temp, min_temp, auto_temperature) = match config.temperature {
+        None if !uses_temperature(&config) => (0.0, 0.0, None),
+        None => {
+            return Err(anyhow::anyhow!(
+                "temperatureが指定されていません\nヒント: algorithmとacceptanceがannealingとmetropolis、threshold、great_delugeのいずれかの段階ではtemperatureの指定が必要です"
+            ))
+        }
+        Some(RawTemperature::Fixed(t)) => (t.begin, t.end, None),
+        Some(RawTemperature::Auto(t)) => (
+            0.0,
+            0.0,
+            Some(AutoTemperature {
+                initial_acceptance: t.initial_acceptance,
+                final_acceptance: t.final_acceptance,
+            }),
+        ),
+        Some(RawTemperature::Name(name)) if name == "auto" => {
+            (0.0, 0.0, Some(DEFAULT_AUTO_TEMPERATURE))
+        }
+        Some(RawTemperature::Name(name)) => {
+            return Err(anyhow::anyhow!(
+                "temperatureの指定{}は不正です\nヒント: {{begin = 10000, end = 0}}のような表か\"auto\"を指定してください",
+                name
+            ))
+        }
+    };
+
     let ac = AnnealingConfig {
         step: config.step_count,
         seed: config.seed,
@@ -21,10 +73,180 @@ pub fn convert_annealing_config<SP: FromConfig + Clone>(
             })
             .collect::<anyhow::Result<Vec<Vec<SP>>>>()?
             .concat(),
-        update_func: config.update_function,
-        max_temp: config.temperature.begin,
-        min_temp: config.temperature.end,
+        update_func: convert_update_function(config.update_function),
+        stage_type: match config.stage_type {
+            RawStageType::Annealing => StageType::Annealing,
+            RawStageType::Polish => StageType::Polish,
+        },
+        algorithm: match config.algorithm.unwrap_or_default() {
+            RawAlgorithm::Annealing => Algorithm::Annealing,
+            RawAlgorithm::Tabu => Algorithm::Tabu {
+                tenure: config.tabu_tenure.unwrap_or(DEFAULT_TABU_TENURE),
+                candidate_count: config
+                    .tabu_candidate_count
+                    .unwrap_or(DEFAULT_TABU_CANDIDATE_COUNT),
+            },
+            RawAlgorithm::HillClimbing => Algorithm::HillClimbing,
+        },
+        acceptance: match config.acceptance.unwrap_or_default() {
+            RawAcceptance::Metropolis => Acceptance::Metropolis,
+            RawAcceptance::Threshold => Acceptance::Threshold,
+            RawAcceptance::GreatDeluge => Acceptance::GreatDeluge,
+            RawAcceptance::LateAcceptance => Acceptance::LateAcceptance {
+                length: config
+                    .late_acceptance_length
+                    .unwrap_or(DEFAULT_LATE_ACCEPTANCE_LENGTH),
+            },
+        },
+        adaptive_update: config.adaptive_update,
+        max_temp,
+        min_temp,
+        auto_temperature,
     };
 
     Ok(ac)
 }
+
+/// 温度を用いる段階か
+/// polish、tabu、hill_climbing、late_acceptanceでは用いない
+fn uses_temperature(config: &RawAnnealingConfig) -> bool {
+    config.stage_type == RawStageType::Annealing
+        && config.algorithm.unwrap_or_default() == RawAlgorithm::Annealing
+        && config.acceptance.unwrap_or_default() != RawAcceptance::LateAcceptance
+}
+
+/// 段階の種類やアルゴリズムにより用いられないキーが指定されていないか
+/// 指定した値が黙って無視されないように、エラーにする
+fn check_unused_keys(config: &RawAnnealingConfig) -> anyhow::Result<()> {
+    if config.stage_type == RawStageType::Polish {
+        for (key, specified) in [
+            ("adaptive_update", config.adaptive_update),
+            ("algorithm", config.algorithm.is_some()),
+            ("acceptance", config.acceptance.is_some()),
+        ] {
+            anyhow::ensure!(
+                !specified,
+                "{}はstage_typeがpolishの段階では用いられません\nヒント: {}を削除してください",
+                key,
+                key
+            );
+        }
+    }
+    anyhow::ensure!(
+        config.algorithm.unwrap_or_default() == RawAlgorithm::Annealing
+            || config.acceptance.is_none(),
+        "acceptanceはalgorithmがannealingの段階でのみ用いられます\nヒント: algorithm = \"annealing\"を指定するか、acceptanceを削除してください"
+    );
+    if config.algorithm != Some(RawAlgorithm::Tabu) {
+        for (key, value) in [
+            ("tabu_tenure", config.tabu_tenure),
+            ("tabu_candidate_count", config.tabu_candidate_count),
+        ] {
+            anyhow::ensure!(
+                value.is_none(),
+                "{}はalgorithmがtabuの段階でのみ用いられます\nヒント: algorithm = \"tabu\"を指定するか、{}を削除してください",
+                key,
+                key
+            );
+        }
+    }
+    anyhow::ensure!(
+        config.acceptance == Some(RawAcceptance::LateAcceptance)
+            || config.late_acceptance_length.is_none(),
+        "late_acceptance_lengthはacceptanceがlate_acceptanceの段階でのみ用いられます\nヒント: acceptance = \"late_acceptance\"を指定するか、late_acceptance_lengthを削除してください"
+    );
+    anyhow::ensure!(
+        uses_temperature(config) || config.temperature.is_none(),
+        "temperatureはこの段階では用いられません\nヒント: stage_typeがpolish、algorithmがtabuかhill_climbing、acceptanceがlate_acceptanceの段階ではtemperatureを削除してください"
+    );
+    Ok(())
+}
+
+/// RawUpdateFunctionを更新関数名に変換する
+/// 重み付きの場合は`[(関数名, 重み), ...]`の形式にする
+fn convert_update_function(update_function: RawUpdateFunction) -> String {
+    match update_function {
+        RawUpdateFunction::Name(name) => name,
+        RawUpdateFunction::Weighted(weights) => format!(
+            "[{}]",
+            weights
+                .iter()
+                .map(|(name, w)| format!("({}, {})", name, w))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// tomlの文字列から変換する
+    fn convert(s: &str) -> anyhow::Result<AnnealingConfig<String>> {
+        convert_annealing_config(toml::from_str(s).unwrap())
+    }
+
+    /// 温度を用いない段階ではtemperatureを省略でき、指定するとエラーになるケース
+    #[test]
+    fn test_temperature() {
+        assert!(convert(
+            r#"
+            step_count = 100
+            score_functions = []
+            update_function = "update_range"
+            "#
+        )
+        .is_err());
+
+        for keys in [
+            r#"stage_type = "polish""#,
+            r#"algorithm = "tabu""#,
+            r#"algorithm = "hill_climbing""#,
+            r#"acceptance = "late_acceptance""#,
+        ] {
+            let base = format!(
+                "step_count = 100\nscore_functions = []\nupdate_function = \"update_range\"\n{}\n",
+                keys
+            );
+            let config = convert(&base).unwrap();
+            assert_eq!(config.auto_temperature, None);
+
+            let with_temperature = format!("{}temperature = {{begin = 10, end = 0}}\n", base);
+            assert!(convert(&with_temperature).is_err(), "{}", keys);
+        }
+    }
+
+    /// stage_type、algorithm、acceptanceで用いられないキーを指定するとエラーになるケース
+    #[test]
+    fn test_unused_keys() {
+        let base = r#"
+            step_count = 100
+            score_functions = []
+            update_function = "update_range"
+            "#;
+        for keys in [
+            "tabu_tenure = 5\ntemperature = {begin = 10, end = 0}",
+            "tabu_candidate_count = 5\ntemperature = {begin = 10, end = 0}",
+            "algorithm = \"hill_climbing\"\ntabu_tenure = 5",
+            "late_acceptance_length = 5\ntemperature = {begin = 10, end = 0}",
+            "stage_type = \"polish\"\nadaptive_update = true",
+            "stage_type = \"polish\"\nalgorithm = \"tabu\"",
+            "stage_type = \"polish\"\nalgorithm = \"annealing\"",
+            "stage_type = \"polish\"\nacceptance = \"threshold\"",
+            "algorithm = \"tabu\"\nacceptance = \"threshold\"",
+            "algorithm = \"hill_climbing\"\nacceptance = \"late_acceptance\"",
+        ] {
+            assert!(convert(&format!("{}{}", base, keys)).is_err(), "{}", keys);
+        }
+
+        for keys in [
+            "algorithm = \"tabu\"\ntabu_tenure = 5\ntabu_candidate_count = 5",
+            "acceptance = \"late_acceptance\"\nlate_acceptance_length = 5",
+            "stage_type = \"polish\"\nadaptive_update = false",
+            "adaptive_update = true\nalgorithm = \"hill_climbing\"",
+        ] {
+            assert!(convert(&format!("{}{}", base, keys)).is_ok(), "{}", keys);
+        }
+    }
+}
@@ -4,31 +4,56 @@ use std::collections::HashMap;
 
 use anyhow::Context;
 
-use super::super::reader::types::{RawAttributeTable, RawScheduleConfig};
+use super::super::reader::types::{
+    RawAttributeTable, RawAvailabilityNode, RawCrossover, RawScheduleConfig, RawShiftHoursNode,
+    RawWishNode,
+};
 use kinmu_model::{
-    DayAttributeName, DayConfig, FillConfig, ResultConfig, Schedule, ScheduleConfig, ScheduleState,
-    ScoreFunction, ScoreWarning, Staff, StaffAttributeNameIndexMap, StaffConfig,
+    Availability, Crossover, DayAttributeName, DayConfig, FillConfig, RecombinationConfig,
+    ResultConfig, Schedule, ScheduleConfig, ScheduleState, ScoreFunction, ScoreWarning, Staff,
+    StaffAttributeNameIndexMap, StaffConfig, Wish,
 };
 
 use super::util::parser::*;
 
 /// RawScheduleConfigをScheduleConfigに変換する
-/// annealing_configsフィールドは空なので、あとから設定しなおす
-pub fn convert_schedule_config<SP: FromConfig, S: FromConfig + MapState<SS>, SS, DS: FromConfig>(
+/// annealing_configsフィールドとrecombinationのannealing_configsフィールドは空なので、あとから設定しなおす
+pub fn convert_schedule_config<
+    SP: FromConfig,
+    S: FromConfig + MapState<SS>,
+    SS,
+    DS: FromConfig + PartialEq,
+>(
     config: RawScheduleConfig,
 ) -> anyhow::Result<ScheduleConfig<SP, S, SS, DS>> {
-    let schedule = config
+    let rows = config
         .day
         .requested_schedule
         .iter()
         .map(|s| {
-            <CharVecWrapper<S>>::from_config(s)
+            <CharOrGroupVecWrapper<S>>::from_config(s)
                 .map(|w| w.0)
                 .with_context(|| format!("Failed to parse schedule row \"{}\"", s))
         })
-        .collect::<anyhow::Result<Schedule<S>>>()
+        .collect::<anyhow::Result<Vec<Vec<CharOrGroup<S>>>>>()
+        .context("Failed to parse day.requested_schedule")?;
+
+    let days = <CharVecWrapper<DS>>::from_config(&config.day.states)
+        .context("Failed to parse day.states")?
+        .0;
+
+    let (schedule, mut schedule_states) = make_schedule_and_state(rows, config.day.buffer_count)
         .context("Failed to parse day.requested_schedule")?;
 
+    let availabilities = apply_availability(
+        config.staff.availability,
+        &schedule,
+        &days,
+        config.day.buffer_count,
+        &mut schedule_states,
+    )
+    .context("Failed to parse staff.availability")?;
+
     let staff_config = StaffConfig {
         attribute_map: make_staff_attribute_map(config.staff.attributes),
         list: config
@@ -46,18 +71,27 @@ pub fn convert_schedule_config<SP: FromConfig, S: FromConfig + MapState<SS>, SS,
             .iter()
             .map(|x| (x.from, x.to))
             .collect(),
+        pair_list: config
+            .staff
+            .pair_list
+            .iter()
+            .map(|x| (x.from, x.to))
+            .collect(),
+        availabilities,
         count: config.staff.count,
     };
 
     let day_config = DayConfig {
         count: config.day.day_count,
         buffer_count: config.day.buffer_count,
-        days: <CharVecWrapper<DS>>::from_config(&config.day.states)
-            .context("Failed to parse day.states")?
-            .0,
-        schedule_states: make_schedule_state(&schedule, config.day.buffer_count),
+        days,
+        schedule_states,
         requested_schedule: schedule,
         attributes: make_day_attributes(config.day.attributes),
+        wishes: make_wishes(config.day.wishes, config.day.buffer_count)
+            .context("Failed to parse day.wishes")?,
+        shift_hours: make_shift_hours(config.day.shift_hours)
+            .context("Failed to parse day.shift_hours")?,
     };
 
     let fill_config = FillConfig {
@@ -91,11 +125,23 @@ pub fn convert_schedule_config<SP: FromConfig, S: FromConfig + MapState<SS>, SS,
             .context("Failed to parse result.score_functions")?,
     };
 
+    let recombination = config.annealing.recombination.map(|r| RecombinationConfig {
+        generations: r.generations,
+        crossover: match r.crossover {
+            RawCrossover::Staff => Crossover::Staff,
+            RawCrossover::Week => Crossover::Week,
+        },
+        seed: r.seed,
+        annealing_configs: Default::default(),
+    });
+
     let schedule_config: ScheduleConfig<SP, S, SS, DS> = ScheduleConfig {
+        name: Default::default(),
         staff: staff_config,
         day: day_config,
         fill: fill_config,
         annealing_configs: Default::default(),
+        recombination,
         result: result_config,
     };
 
@@ -110,6 +156,36 @@ fn make_day_attributes(attributes: Vec<RawAttributeTable>) -> HashMap<DayAttribu
     ans
 }
 
+/// 希望のリストを変換する
+/// 日付はバッファーを除いて1,2,3..と続くので、表の列の番号に変換する
+fn make_wishes<S: FromConfig>(
+    wishes: Vec<RawWishNode>,
+    buffer: usize,
+) -> anyhow::Result<Vec<Wish<S>>> {
+    wishes
+        .into_iter()
+        .map(|w| {
+            anyhow::ensure!(0 < w.day, "日付は1から始まります");
+            Ok(Wish {
+                staff: w.staff,
+                day: buffer + w.day - 1,
+                shift: <S>::from_config(&w.shift)?,
+                priority: w.priority,
+            })
+        })
+        .collect()
+}
+
+/// シフトごとの勤務時間のリストを変換する
+fn make_shift_hours<S: FromConfig>(
+    shift_hours: Vec<RawShiftHoursNode>,
+) -> anyhow::Result<Vec<(S, f32)>> {
+    shift_hours
+        .into_iter()
+        .map(|h| Ok((<S>::from_config(&h.shift)?, h.hours)))
+        .collect()
+}
+
 fn make_staff_attribute_map(attributes: Vec<String>) -> StaffAttributeNameIndexMap {
     let mut name_to_index = HashMap::new();
     for (i, name) in attributes.iter().enumerate() {
@@ -126,30 +202,312 @@ fn make_staff_attribute_map(attributes: Vec<String>) -> StaffAttributeNameIndexM
 pub trait MapState<SS> {
     /// バッファの範囲に対して適応するデフォルトのShiftStateを指定
     const BUFFER_CASE: SS;
+    /// requested_scheduleで[NK]のように指定された枠に入れる未定のシフトを指定
+    const FREE_CASE: Self;
     /// SSに変換する
     fn to_state(&self) -> SS;
+    /// 指定したシフトのいずれかしか入れられない場合のSSに変換する
+    /// 希望で固定された枠などは、制限を無視してto_stateと同じ結果を返してよい
+    fn to_restricted_state(&self, shifts: &[Self]) -> SS
+    where
+        Self: Sized;
+    /// 既にstateである枠を、さらに指定したシフトのいずれかしか入れられないよう制限したSSを返す
+    /// 入れられるシフトがなくなる場合はNoneを返す
+    /// 希望で固定された枠は、そのシフトが指定したシフトに含まれない場合にNoneを返す
+    fn restrict_state(&self, state: &SS, shifts: &[Self]) -> Option<SS>
+    where
+        Self: Sized;
 }
 
-/// ScheduleStateを与えられた表から生成する
+/// requested_scheduleを読み込んだ結果から表とScheduleStateを生成する
 /// シフトとして用いるSにはMapStateを要求
-fn make_schedule_state<S: MapState<SS>, SS>(
-    schedule: &Schedule<S>,
+/// [NK]のように指定された枠はFREE_CASEとし、指定されたシフトのいずれかしか入れられないようにする
+fn make_schedule_and_state<S: MapState<SS>, SS>(
+    rows: Vec<Vec<CharOrGroup<S>>>,
     buffer: usize,
-) -> ScheduleState<SS> {
-    let mut ans: ScheduleState<SS> = Vec::new();
-    for line in schedule {
-        ans.push(
-            line.iter()
-                .enumerate()
-                .map(|(i, shift)| {
-                    if i < buffer {
+) -> anyhow::Result<(Schedule<S>, ScheduleState<SS>)> {
+    let mut schedule: Schedule<S> = Vec::new();
+    let mut schedule_states: ScheduleState<SS> = Vec::new();
+    for (r, row) in rows.into_iter().enumerate() {
+        let mut line = Vec::new();
+        let mut states = Vec::new();
+        for (i, cell) in row.into_iter().enumerate() {
+            match cell {
+                CharOrGroup::Char(shift) => {
+                    states.push(if i < buffer {
                         S::BUFFER_CASE
                     } else {
                         shift.to_state()
-                    }
-                })
-                .collect(),
+                    });
+                    line.push(shift);
+                }
+                CharOrGroup::Group(shifts) => {
+                    anyhow::ensure!(
+                        buffer <= i,
+                        "{}行目のバッファーに[]が指定されています",
+                        r + 1
+                    );
+                    let shift = S::FREE_CASE;
+                    states.push(shift.to_restricted_state(&shifts));
+                    line.push(shift);
+                }
+            }
+        }
+        schedule.push(line);
+        schedule_states.push(states);
+    }
+    Ok((schedule, schedule_states))
+}
+
+/// staff.availabilityをScheduleStateに反映し、StaffConfigで用いるAvailabilityを生成する
+/// 勤務できない日付を反映した後、それ以外の日付に曜日ごとに入れてよいシフトを反映する
+/// requested_scheduleで[NK]のように制限された枠は、その制限と共通するシフトのみ入れてよいとする
+/// 日付はバッファーを除いて1,2,3..と続くので、表の列の番号に変換する
+fn apply_availability<S: FromConfig + MapState<SS>, SS, DS: FromConfig + PartialEq>(
+    availability: Vec<RawAvailabilityNode>,
+    schedule: &Schedule<S>,
+    days: &[DS],
+    buffer: usize,
+    schedule_states: &mut ScheduleState<SS>,
+) -> anyhow::Result<Vec<Availability>> {
+    let mut ans = Vec::new();
+    for (i, node) in availability.into_iter().enumerate() {
+        let r = node.staff;
+        anyhow::ensure!(
+            r < schedule.len(),
+            "{}番目のstaff({})がrequested_scheduleの行数({})以上です",
+            i + 1,
+            r,
+            schedule.len()
+        );
+
+        // 枠を制限し、入れられるシフトがなくなるか固定されたシフトと矛盾する場合はエラーを返す
+        let mut restrict = |c: usize, shifts: &[S]| -> anyhow::Result<()> {
+            schedule_states[r][c] = schedule[r][c]
+                .restrict_state(&schedule_states[r][c], shifts)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{}番目のstaff({})の{}日目に入れられるシフトがないか、固定されたシフトと矛盾します",
+                        i + 1,
+                        r,
+                        c + 1 - buffer
+                    )
+                })?;
+            Ok(())
+        };
+
+        let unavailable_shifts = <CharVecWrapper<S>>::from_config(&node.unavailable_shifts)?.0;
+        anyhow::ensure!(
+            node.unavailable.is_empty() || !unavailable_shifts.is_empty(),
+            "{}番目のunavailable_shiftsが指定されていません",
+            i + 1
+        );
+        let mut unavailable_ranges = Vec::new();
+        for range in &node.unavailable {
+            anyhow::ensure!(
+                0 < range.from && range.from <= range.to,
+                "{}番目のunavailableの範囲({}~{})が不正です",
+                i + 1,
+                range.from,
+                range.to
+            );
+            let (from, to) = (buffer + range.from - 1, buffer + range.to - 1);
+            anyhow::ensure!(
+                to < schedule[r].len(),
+                "{}番目のunavailableの範囲({}~{})が日数を超えています",
+                i + 1,
+                range.from,
+                range.to
+            );
+            for c in from..=to {
+                restrict(c, &unavailable_shifts)?;
+            }
+            unavailable_ranges.push((from, to));
+        }
+
+        for allowed in &node.allowed_shifts {
+            let day_state = <DS>::from_config(&allowed.day_state)?;
+            let shifts = <CharVecWrapper<S>>::from_config(&allowed.shifts)?.0;
+            anyhow::ensure!(
+                !shifts.is_empty(),
+                "{}番目のallowed_shiftsのshiftsが指定されていません",
+                i + 1
+            );
+            for c in buffer..schedule[r].len() {
+                // 勤務できない日付は、曜日ごとの指定より優先する
+                if unavailable_ranges
+                    .iter()
+                    .any(|&(from, to)| from <= c && c <= to)
+                {
+                    continue;
+                }
+                if days.get(c) == Some(&day_state) {
+                    restrict(c, &shifts)?;
+                }
+            }
+        }
+
+        ans.push(Availability {
+            staff: r,
+            unavailable_ranges,
+            max_shifts_per_week: node.max_shifts_per_week,
+        });
+    }
+    Ok(ans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::reader::types::{RawAllowedShifts, RawDayRange};
+    use super::*;
+
+    /// テスト用のシフト
+    /// Uは未定
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum TestShift {
+        U,
+        N,
+        K,
+        Y,
+    }
+
+    /// テスト用のシフトの状態
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestState {
+        Absolute,
+        Random,
+        OneOf(Vec<TestShift>),
+    }
+
+    impl FromConfig for TestShift {
+        fn from_config(s: &str) -> anyhow::Result<Self> {
+            match s {
+                "U" => Ok(TestShift::U),
+                "N" => Ok(TestShift::N),
+                "K" => Ok(TestShift::K),
+                "Y" => Ok(TestShift::Y),
+                _ => Err(anyhow::anyhow!("Failed to parse TestShift: {}", s)),
+            }
+        }
+    }
+
+    impl MapState<TestState> for TestShift {
+        const BUFFER_CASE: TestState = TestState::Absolute;
+        const FREE_CASE: TestShift = TestShift::U;
+        fn to_state(&self) -> TestState {
+            match self {
+                TestShift::U => TestState::Random,
+                _ => TestState::Absolute,
+            }
+        }
+        fn to_restricted_state(&self, shifts: &[Self]) -> TestState {
+            match self {
+                TestShift::U => TestState::OneOf(shifts.to_vec()),
+                _ => TestState::Absolute,
+            }
+        }
+        fn restrict_state(&self, state: &TestState, shifts: &[Self]) -> Option<TestState> {
+            match state {
+                TestState::Absolute => shifts.contains(self).then_some(TestState::Absolute),
+                TestState::Random => Some(self.to_restricted_state(shifts)),
+                TestState::OneOf(current) => {
+                    let common = current
+                        .iter()
+                        .filter(|s| shifts.contains(s))
+                        .copied()
+                        .collect::<Vec<_>>();
+                    (!common.is_empty()).then_some(TestState::OneOf(common))
+                }
+            }
+        }
+    }
+
+    /// 1行の希望された表から表とScheduleStateを作る
+    fn schedule_and_state(row: &str) -> (Schedule<TestShift>, ScheduleState<TestState>) {
+        let rows = vec![
+            <CharOrGroupVecWrapper<TestShift>>::from_config(row)
+                .unwrap()
+                .0,
+        ];
+        make_schedule_and_state(rows, 0).unwrap()
+    }
+
+    fn node(unavailable_shifts: &str, allowed: Vec<RawAllowedShifts>) -> RawAvailabilityNode {
+        RawAvailabilityNode {
+            staff: 0,
+            unavailable: vec![RawDayRange { from: 1, to: 2 }],
+            unavailable_shifts: unavailable_shifts.to_string(),
+            allowed_shifts: allowed,
+            max_shifts_per_week: None,
+        }
+    }
+
+    /// [NK]の枠を勤務できない日付でKYに制限すると、共通するKのみになるケース
+    /// 勤務できない日付は曜日ごとの指定より優先される
+    #[test]
+    fn apply_availability_intersects_one_of() {
+        let (schedule, mut states) = schedule_and_state("[NK]UU");
+        let allowed = vec![RawAllowedShifts {
+            day_state: "1".to_string(),
+            shifts: "N".to_string(),
+        }];
+        apply_availability(
+            vec![node("KY", allowed)],
+            &schedule,
+            &[1, 1, 1],
+            0,
+            &mut states,
+        )
+        .unwrap();
+        assert_eq!(
+            states[0],
+            vec![
+                TestState::OneOf(vec![TestShift::K]),
+                TestState::OneOf(vec![TestShift::K, TestShift::Y]),
+                TestState::OneOf(vec![TestShift::N]),
+            ]
+        );
+    }
+
+    /// 共通するシフトがない場合、エラーを返すケース
+    #[test]
+    fn apply_availability_empty_intersection() {
+        let (schedule, mut states) = schedule_and_state("[NK]UU");
+        assert!(apply_availability(
+            vec![node("Y", vec![])],
+            &schedule,
+            &[1, 1, 1],
+            0,
+            &mut states
+        )
+        .is_err());
+    }
+
+    /// 固定された枠のシフトが入れてよいシフトに含まれない場合、エラーを返すケース
+    #[test]
+    fn apply_availability_conflicts_with_fixed_cell() {
+        let (schedule, mut states) = schedule_and_state("KNU");
+        assert!(apply_availability(
+            vec![node("KY", vec![])],
+            &schedule,
+            &[1, 1, 1],
+            0,
+            &mut states
+        )
+        .is_err());
+
+        let (schedule, mut states) = schedule_and_state("KYU");
+        apply_availability(
+            vec![node("KY", vec![])],
+            &schedule,
+            &[1, 1, 1],
+            0,
+            &mut states,
+        )
+        .unwrap();
+        assert_eq!(
+            states[0],
+            vec![TestState::Absolute, TestState::Absolute, TestState::Random]
         );
     }
-    ans
 }
@@ -260,8 +260,49 @@ where
     }
 }
 
+/// CharOrGroupVecWrapperの要素
+/// 1文字ならChar、[]で囲まれた部分ならGroup
+#[derive(Debug, PartialEq, Clone)]
+pub enum CharOrGroup<T> {
+    Char(T),
+    Group(Vec<T>),
+}
+
+/// FromConfigを文字ごとに変換してVecを作るためのWrapper
+/// ただし、[]で囲まれた部分はまとめて1つの要素とする
+pub struct CharOrGroupVecWrapper<T>(pub Vec<CharOrGroup<T>>);
+
+impl<T> FromConfig for CharOrGroupVecWrapper<T>
+where
+    T: FromConfig,
+{
+    fn from_config(s: &str) -> anyhow::Result<Self> {
+        let mut ans = Vec::new();
+        let mut group: Option<Vec<T>> = None;
+        for c in s.chars() {
+            match (c, &mut group) {
+                ('[', None) => group = Some(Vec::new()),
+                ('[', Some(_)) => Err(anyhow::anyhow!("\'[\' is nested in {}", s))?,
+                (']', None) => Err(anyhow::anyhow!("\'[\' not found in {}", s))?,
+                (']', Some(g)) => {
+                    if g.is_empty() {
+                        Err(anyhow::anyhow!("Empty group found in {}", s))?;
+                    }
+                    ans.push(CharOrGroup::Group(group.take().unwrap()));
+                }
+                (c, Some(g)) => g.push(T::from_config(&c.to_string())?),
+                (c, None) => ans.push(CharOrGroup::Char(T::from_config(&c.to_string())?)),
+            }
+        }
+        if group.is_some() {
+            Err(anyhow::anyhow!("\']\' not found in {}", s))?;
+        }
+        Ok(CharOrGroupVecWrapper(ans))
+    }
+}
+
 /// Vecを読み込む
-/// 多重入れ子構造になったVecにも対応
+/// 多重入れ子構造になったVecや、タプルを要素にもつVecにも対応
 fn format_str_vec_to_words(s: &str) -> anyhow::Result<Vec<&str>> {
     let trimmed_s = s.trim();
     if !trimmed_s.starts_with('[') {
@@ -272,19 +313,19 @@ fn format_str_vec_to_words(s: &str) -> anyhow::Result<Vec<&str>> {
     }
     let bare_s = &trimmed_s[1..(trimmed_s.len() - 1)];
     let mut words = Vec::new();
-    let mut bracket_flag = false;
+    let mut bracket_count = 0;
     let mut start_idx = 0;
     let mut end_idx = 0;
     for c in bare_s.chars() {
-        if !bracket_flag && c == ',' {
+        if bracket_count == 0 && c == ',' {
             words.push(bare_s[start_idx..end_idx].trim());
             start_idx = end_idx + c.len_utf8();
         }
-        if c == '[' {
-            bracket_flag = true;
+        if c == '(' || c == '[' {
+            bracket_count += 1;
         }
-        if c == ']' {
-            bracket_flag = false;
+        if c == ')' || c == ']' {
+            bracket_count -= 1;
         }
         end_idx += c.len_utf8();
     }
@@ -386,10 +427,37 @@ mod tests {
         assert_eq!(v2.0, vec![vec![0, 1, 2], vec![3, 4, 5]]);
     }
 
+    #[test]
+    fn vec_tuple_test() {
+        let v = <VecWrapper<(String, f32)>>::from_config("[(N, 8), (I, 16.5)]").unwrap();
+
+        assert_eq!(
+            v.0,
+            vec![(String::from("N"), 8.0), (String::from("I"), 16.5)]
+        );
+    }
+
     #[test]
     fn char_vec_wrapper_test() {
         let v2 = <CharVecWrapper<i32>>::from_config("123456").unwrap();
 
         assert_eq!(v2.0, vec![1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn char_or_group_vec_wrapper_test() {
+        let v = <CharOrGroupVecWrapper<i32>>::from_config("1[23]4").unwrap();
+
+        assert_eq!(
+            v.0,
+            vec![
+                CharOrGroup::Char(1),
+                CharOrGroup::Group(vec![2, 3]),
+                CharOrGroup::Char(4)
+            ]
+        );
+
+        assert!(<CharOrGroupVecWrapper<i32>>::from_config("1[23").is_err());
+        assert!(<CharOrGroupVecWrapper<i32>>::from_config("1[]").is_err());
+    }
 }
@@ -6,28 +6,34 @@ use anyhow::Context;
 use std::path::Path;
 
 use kinmu_core::Input;
-use kinmu_model::MainConfig;
+use kinmu_model::{AnnealingConfig, MainConfig};
 
 mod checker;
 mod converter;
 mod reader;
 
 // トレイトを外部に公開
-pub use checker::Check;
+pub use checker::{unknown_function_error, Check, CheckFunction};
 pub use converter::*;
 
 /// toml形式のconfigファイルを読み込む入力器
-/// main_configのパスを保持
+/// main_configのパスと、関数名をチェックするためのfill, updateを保持
 #[derive(Debug)]
-pub struct InputByFile<'a> {
+pub struct InputByFile<'a, F, U> {
     main_config_path: &'a str,
+    fill: F,
+    update: U,
 }
 
-impl<'a> InputByFile<'a> {
+impl<'a, F, U> InputByFile<'a, F, U> {
     /// コンストラクタ
-    /// main_configのパスを要求
-    pub fn new(main_config_path: &'a str) -> Self {
-        InputByFile { main_config_path }
+    /// main_configのパスと、生成器で用いるfill, updateを要求
+    pub fn new(main_config_path: &'a str, fill: F, update: U) -> Self {
+        InputByFile {
+            main_config_path,
+            fill,
+            update,
+        }
     }
 }
 
@@ -35,11 +41,15 @@ impl<'a> InputByFile<'a> {
 /// ScorePropにあたるSPにはCloneとCheckを要求
 /// ShiftにあたるSにはMapStateを要求
 /// SP, S, DayStateにあたるDSにはそれぞれFromConfigを要求
-impl<SP, S, SS, DS> Input<MainConfig<SP, S, SS, DS>> for InputByFile<'_>
+/// また、DSには曜日ごとの制限を反映するためにPartialEqを要求
+/// F, Uには関数名をチェックするためにCheckFunctionを要求
+impl<SP, S, SS, DS, F, U> Input<MainConfig<SP, S, SS, DS>> for InputByFile<'_, F, U>
 where
     SP: FromConfig + Clone + Check<SP, S, SS, DS>,
     S: FromConfig + MapState<SS>,
-    DS: FromConfig,
+    DS: FromConfig + PartialEq,
+    F: CheckFunction<SP, S, SS, DS>,
+    U: CheckFunction<SP, S, SS, DS>,
 {
     fn load_config(&mut self) -> anyhow::Result<MainConfig<SP, S, SS, DS>> {
         // configが格納されているフォルダのパス
@@ -69,6 +79,12 @@ where
                 )
             })?;
             let annealing_config_paths = raw_schedule.annealing.config_paths.clone();
+            let recombination_config_paths = raw_schedule
+                .annealing
+                .recombination
+                .as_ref()
+                .map(|r| r.config_paths.clone())
+                .unwrap_or_default();
             let mut converted_schedule = converter::convert_schedule_config(raw_schedule)
                 .with_context(|| {
                     format!(
@@ -76,32 +92,45 @@ where
                         path.display(),
                     )
                 })?;
+            converted_schedule.name = path.display().to_string();
 
             for path in annealing_config_paths {
-                let path = config_root_path.join(path);
-                let raw_annealing = reader::read_annealing_config(&path).with_context(|| {
-                    format!(
-                        "[エラー] 焼きなましconfigの読み込みに失敗しました\n対象ファイル: {}",
-                        path.display(),
-                    )
-                })?;
-                let converted_annealing = converter::convert_annealing_config(raw_annealing)
-                    .with_context(|| {
-                        anyhow::anyhow!(
-                            "[エラー] 焼きなましconfigの読み込みに失敗しました\n対象ファイル: {}",
-                            path.display(),
-                        )
-                    })?;
                 converted_schedule
                     .annealing_configs
-                    .push(converted_annealing);
+                    .push(load_annealing_config(&config_root_path.join(path))?);
+            }
+            if let Some(recombination) = &mut converted_schedule.recombination {
+                for path in recombination_config_paths {
+                    recombination
+                        .annealing_configs
+                        .push(load_annealing_config(&config_root_path.join(path))?);
+                }
             }
 
             converted_main.schedule_configs.push(converted_schedule);
         }
 
-        checker::run(&converted_main).context("[エラー] configの変換チェックに失敗しました")?;
+        checker::run(&converted_main, &self.fill, &self.update)
+            .context("[エラー] configの変換チェックに失敗しました")?;
 
         Ok(converted_main)
     }
 }
+
+/// 焼きなましconfigを読み込み、変換する
+fn load_annealing_config<SP: FromConfig + Clone>(
+    path: &Path,
+) -> anyhow::Result<AnnealingConfig<SP>> {
+    let raw_annealing = reader::read_annealing_config(path).with_context(|| {
+        format!(
+            "[エラー] 焼きなましconfigの読み込みに失敗しました\n対象ファイル: {}",
+            path.display(),
+        )
+    })?;
+    converter::convert_annealing_config(raw_annealing).with_context(|| {
+        anyhow::anyhow!(
+            "[エラー] 焼きなましconfigの読み込みに失敗しました\n対象ファイル: {}",
+            path.display(),
+        )
+    })
+}
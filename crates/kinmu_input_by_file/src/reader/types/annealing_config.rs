@@ -1,12 +1,63 @@
 //! annealing_configを読み込むための構造体
 
+use std::collections::BTreeMap;
+
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
 pub struct RawAnnealingConfig {
     pub step_count: u32,
     pub seed: Option<u64>,
     pub score_functions: Vec<RawAnnealingScoreFunction>,
-    pub update_function: String,
-    pub temperature: RawTemperatureKey,
+    pub update_function: RawUpdateFunction,
+    #[serde(default)]
+    pub adaptive_update: bool,
+    #[serde(default)]
+    pub stage_type: RawStageType,
+    pub algorithm: Option<RawAlgorithm>,
+    pub tabu_tenure: Option<u32>,
+    pub tabu_candidate_count: Option<u32>,
+    pub acceptance: Option<RawAcceptance>,
+    pub late_acceptance_length: Option<u32>,
+    /// stage_typeがpolishの場合は省略できる
+    pub temperature: Option<RawTemperature>,
+}
+
+/// 更新関数の指定
+/// 関数名1つか、関数名とその重みのテーブルで指定する
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum RawUpdateFunction {
+    Name(String),
+    Weighted(BTreeMap<String, f32>),
+}
+
+/// 段階の種類の指定
+#[derive(Debug, PartialEq, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawStageType {
+    #[default]
+    Annealing,
+    Polish,
+}
+
+/// 探索のアルゴリズムの指定
+#[derive(Debug, PartialEq, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawAlgorithm {
+    #[default]
+    Annealing,
+    Tabu,
+    HillClimbing,
+}
+
+/// 受理の基準の指定
+#[derive(Debug, PartialEq, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawAcceptance {
+    #[default]
+    Metropolis,
+    Threshold,
+    GreatDeluge,
+    LateAcceptance,
 }
 
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
@@ -14,6 +65,53 @@ pub struct RawAnnealingScoreFunction {
     pub scores: Vec<String>,
 }
 
+/// 温度の指定
+/// beginとendの表か、目標の受理率の表か、"auto"で指定する
+#[derive(Debug, PartialEq, Clone)]
+pub enum RawTemperature {
+    Fixed(RawTemperatureKey),
+    Auto(RawAutoTemperature),
+    Name(String),
+}
+
+/// 表の場合、beginかendを含むかでどちらの表か決めてから読み込む
+/// untaggedでは、表の値が不正な場合にどの値が不正か分からないため
+impl<'de> serde::Deserialize<'de> for RawTemperature {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        match toml::Value::deserialize(deserializer)? {
+            toml::Value::String(name) => Ok(RawTemperature::Name(name)),
+            toml::Value::Table(table)
+                if table.contains_key("begin") || table.contains_key("end") =>
+            {
+                RawTemperatureKey::deserialize(toml::Value::Table(table))
+                    .map(RawTemperature::Fixed)
+                    .map_err(|e| {
+                        D::Error::custom(format!("temperatureのbeginとendの表が不正です: {}", e))
+                    })
+            }
+            toml::Value::Table(table) => RawAutoTemperature::deserialize(toml::Value::Table(table))
+                .map(RawTemperature::Auto)
+                .map_err(|e| {
+                    D::Error::custom(format!(
+                        "temperatureのinitial_acceptanceとfinal_acceptanceの表が不正です: {}",
+                        e
+                    ))
+                }),
+            v => Err(D::Error::custom(format!(
+                "temperatureには表か文字列を指定してください: {}",
+                v
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawAutoTemperature {
+    pub initial_acceptance: f32,
+    pub final_acceptance: f32,
+}
+
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
 pub struct RawTemperatureKey {
     pub begin: f32,
@@ -45,6 +143,8 @@ mod tests {
             ]
 
             update_function = "swap"
+            adaptive_update = true
+            stage_type = "polish"
 
             temperature = {begin = 1000, end = 50}
             "#,
@@ -64,13 +164,18 @@ mod tests {
                 },
             ]
         );
-        assert_eq!(config.update_function, "swap");
+        assert_eq!(
+            config.update_function,
+            RawUpdateFunction::Name(String::from("swap"))
+        );
+        assert!(config.adaptive_update);
+        assert_eq!(config.stage_type, RawStageType::Polish);
         assert_eq!(
             config.temperature,
-            RawTemperatureKey {
+            Some(RawTemperature::Fixed(RawTemperatureKey {
                 begin: 1000.0,
                 end: 50.0,
-            }
+            }))
         );
     }
 
@@ -92,13 +197,149 @@ mod tests {
             config.score_functions,
             <Vec<RawAnnealingScoreFunction>>::new()
         );
-        assert_eq!(config.update_function, "");
+        assert_eq!(
+            config.update_function,
+            RawUpdateFunction::Name(String::new())
+        );
+        assert!(!config.adaptive_update);
+        assert_eq!(config.stage_type, RawStageType::Annealing);
+        assert_eq!(config.algorithm, None);
+        assert_eq!(config.tabu_tenure, None);
+        assert_eq!(config.acceptance, None);
         assert_eq!(
             config.temperature,
-            RawTemperatureKey {
+            Some(RawTemperature::Fixed(RawTemperatureKey {
                 begin: 10.0,
                 end: 0.0
-            }
+            }))
         );
     }
+
+    #[test]
+    fn test_weighted_update_function_read() {
+        let config: RawAnnealingConfig = toml::from_str(
+            r#"
+            step_count = 100
+            score_functions = []
+            update_function = {update_range = 0.3, update_iak_safe = 0.7}
+            temperature = {begin = 10, end = 0}
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.update_function,
+            RawUpdateFunction::Weighted(BTreeMap::from([
+                (String::from("update_range"), 0.3),
+                (String::from("update_iak_safe"), 0.7),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_tabu_read() {
+        let config: RawAnnealingConfig = toml::from_str(
+            r#"
+            step_count = 100
+            score_functions = []
+            update_function = "update_range"
+            algorithm = "tabu"
+            tabu_tenure = 5
+            tabu_candidate_count = 30
+            temperature = {begin = 10, end = 0}
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.algorithm, Some(RawAlgorithm::Tabu));
+        assert_eq!(config.tabu_tenure, Some(5));
+        assert_eq!(config.tabu_candidate_count, Some(30));
+    }
+
+    #[test]
+    fn test_acceptance_read() {
+        let config: RawAnnealingConfig = toml::from_str(
+            r#"
+            step_count = 100
+            score_functions = []
+            update_function = "update_range"
+            acceptance = "late_acceptance"
+            late_acceptance_length = 50
+            temperature = {begin = 10, end = 0}
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.acceptance, Some(RawAcceptance::LateAcceptance));
+        assert_eq!(config.late_acceptance_length, Some(50));
+    }
+
+    #[test]
+    fn test_auto_temperature_read() {
+        let config: RawAnnealingConfig = toml::from_str(
+            r#"
+            step_count = 100
+            score_functions = []
+            update_function = "update_range"
+            temperature = "auto"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.temperature,
+            Some(RawTemperature::Name(String::from("auto")))
+        );
+
+        let config: RawAnnealingConfig = toml::from_str(
+            r#"
+            step_count = 100
+            score_functions = []
+            update_function = "update_range"
+            temperature = {initial_acceptance = 0.5, final_acceptance = 0.01}
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.temperature,
+            Some(RawTemperature::Auto(RawAutoTemperature {
+                initial_acceptance: 0.5,
+                final_acceptance: 0.01,
+            }))
+        );
+    }
+
+    /// temperatureの表が不正な場合、不正な値がエラーに含まれるケース
+    #[test]
+    fn test_invalid_temperature_read() {
+        for (temperature, expected) in [
+            ("{begin = 10}", "end"),
+            ("{begin = \"high\", end = 0}", "high"),
+            ("{initial_acceptance = 0.5}", "final_acceptance"),
+            ("10", "表か文字列"),
+        ] {
+            let e = toml::from_str::<RawAnnealingConfig>(&format!(
+                "step_count = 100\nscore_functions = []\nupdate_function = \"update_range\"\ntemperature = {}\n",
+                temperature
+            ))
+            .unwrap_err();
+            assert!(e.to_string().contains(expected), "{}", e);
+        }
+    }
+
+    /// polishの段階ではtemperatureを省略できる
+    #[test]
+    fn test_polish_without_temperature_read() {
+        let config: RawAnnealingConfig = toml::from_str(
+            r#"
+            step_count = 100
+            score_functions = []
+            update_function = "update_noh_repeat"
+            stage_type = "polish"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.stage_type, RawStageType::Polish);
+        assert_eq!(config.temperature, None);
+    }
 }
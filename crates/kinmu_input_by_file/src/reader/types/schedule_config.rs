@@ -16,6 +16,10 @@ pub struct RawStaffTable {
     pub attributes: Vec<String>,
     pub list: Vec<RawStaffListNode>,
     pub ng_list: Vec<RawNGListNode>,
+    #[serde(default)]
+    pub pair_list: Vec<RawPairListNode>,
+    #[serde(default)]
+    pub availability: Vec<RawAvailabilityNode>,
     pub count: usize,
 }
 
@@ -31,6 +35,36 @@ pub struct RawNGListNode {
     pub to: usize,
 }
 
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawPairListNode {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawAvailabilityNode {
+    pub staff: usize,
+    #[serde(default)]
+    pub unavailable: Vec<RawDayRange>,
+    #[serde(default)]
+    pub unavailable_shifts: String,
+    #[serde(default)]
+    pub allowed_shifts: Vec<RawAllowedShifts>,
+    pub max_shifts_per_week: Option<i32>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawDayRange {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawAllowedShifts {
+    pub day_state: String,
+    pub shifts: String,
+}
+
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
 pub struct RawDayTable {
     pub day_count: usize,
@@ -38,6 +72,10 @@ pub struct RawDayTable {
     pub states: String,
     pub requested_schedule: Vec<String>,
     pub attributes: Vec<RawAttributeTable>,
+    #[serde(default)]
+    pub wishes: Vec<RawWishNode>,
+    #[serde(default)]
+    pub shift_hours: Vec<RawShiftHoursNode>,
 }
 
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
@@ -46,6 +84,20 @@ pub struct RawAttributeTable {
     pub values: Vec<i32>,
 }
 
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawWishNode {
+    pub staff: usize,
+    pub day: usize,
+    pub shift: String,
+    pub priority: Score,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawShiftHoursNode {
+    pub shift: String,
+    pub hours: f32,
+}
+
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
 pub struct RawFillTable {
     pub function: String,
@@ -55,6 +107,25 @@ pub struct RawFillTable {
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
 pub struct RawAnnealingTable {
     pub config_paths: Vec<String>,
+    pub recombination: Option<RawRecombinationTable>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+pub struct RawRecombinationTable {
+    pub generations: u32,
+    #[serde(default)]
+    pub crossover: RawCrossover,
+    pub seed: Option<u64>,
+    pub config_paths: Vec<String>,
+}
+
+/// 交叉の方法の指定
+#[derive(Debug, PartialEq, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawCrossover {
+    #[default]
+    Staff,
+    Week,
 }
 
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
@@ -102,6 +173,14 @@ mod tests {
             {from = 0, to = 1},
             ]
 
+            pair_list = [
+            {from = 2, to = 0},
+            ]
+
+            availability = [
+            {staff = 1, unavailable = [{from = 2, to = 3}], unavailable_shifts = "K", allowed_shifts = [{day_state = "A", shifts = "NK"}], max_shifts_per_week = 4},
+            ]
+
             count = 3
 
             [day]
@@ -120,6 +199,10 @@ mod tests {
 
             attributes = [{name = "DayAttribute", values = [1, 2, 1]}]
 
+            wishes = [{staff = 1, day = 2, shift = "K", priority = 2.0}]
+
+            shift_hours = [{shift = "N", hours = 7.5}]
+
             [fill]
             function = "fill_func"
             seed = 2048
@@ -130,6 +213,11 @@ mod tests {
                 "path_b.toml",
             ]
 
+            [annealing.recombination]
+            generations = 3
+            crossover = "week"
+            config_paths = ["path_c.toml"]
+
             [result]
             score_functions = [
             {display_name = "sf1", scores = [
@@ -170,6 +258,23 @@ mod tests {
             ]
         );
         assert_eq!(config.staff.ng_list, vec![RawNGListNode { from: 0, to: 1 }]);
+        assert_eq!(
+            config.staff.pair_list,
+            vec![RawPairListNode { from: 2, to: 0 }]
+        );
+        assert_eq!(
+            config.staff.availability,
+            vec![RawAvailabilityNode {
+                staff: 1,
+                unavailable: vec![RawDayRange { from: 2, to: 3 }],
+                unavailable_shifts: String::from("K"),
+                allowed_shifts: vec![RawAllowedShifts {
+                    day_state: String::from("A"),
+                    shifts: String::from("NK"),
+                }],
+                max_shifts_per_week: Some(4),
+            }]
+        );
         assert_eq!(config.staff.count, 3);
 
         assert_eq!(config.day.day_count, 5);
@@ -190,6 +295,22 @@ mod tests {
                 values: vec![1, 2, 1],
             }]
         );
+        assert_eq!(
+            config.day.wishes,
+            vec![RawWishNode {
+                staff: 1,
+                day: 2,
+                shift: String::from("K"),
+                priority: 2.0,
+            }]
+        );
+        assert_eq!(
+            config.day.shift_hours,
+            vec![RawShiftHoursNode {
+                shift: String::from("N"),
+                hours: 7.5,
+            }]
+        );
 
         assert_eq!(config.fill.function, "fill_func");
         assert_eq!(config.fill.seed, Some(2048));
@@ -198,6 +319,15 @@ mod tests {
             config.annealing.config_paths,
             vec![String::from("path_a.toml"), String::from("path_b.toml")]
         );
+        assert_eq!(
+            config.annealing.recombination,
+            Some(RawRecombinationTable {
+                generations: 3,
+                crossover: RawCrossover::Week,
+                seed: None,
+                config_paths: vec![String::from("path_c.toml")],
+            })
+        );
 
         assert_eq!(
             config.result.score_functions,
@@ -251,6 +381,8 @@ mod tests {
         assert_eq!(config.staff.attributes, <Vec<String>>::new());
         assert_eq!(config.staff.list, <Vec<RawStaffListNode>>::new());
         assert_eq!(config.staff.ng_list, <Vec<RawNGListNode>>::new());
+        assert_eq!(config.staff.pair_list, <Vec<RawPairListNode>>::new());
+        assert_eq!(config.staff.availability, <Vec<RawAvailabilityNode>>::new());
         assert_eq!(config.staff.count, 0);
 
         assert_eq!(config.day.day_count, 0);
@@ -258,11 +390,14 @@ mod tests {
         assert_eq!(config.day.states, "");
         assert_eq!(config.day.requested_schedule, <Vec<String>>::new());
         assert_eq!(config.day.attributes, <Vec<RawAttributeTable>>::new());
+        assert_eq!(config.day.wishes, <Vec<RawWishNode>>::new());
+        assert_eq!(config.day.shift_hours, <Vec<RawShiftHoursNode>>::new());
 
         assert_eq!(config.fill.function, "");
         assert_eq!(config.fill.seed, None);
 
         assert_eq!(config.annealing.config_paths, <Vec<String>>::new());
+        assert_eq!(config.annealing.recombination, None);
 
         assert_eq!(
             config.result.score_functions,
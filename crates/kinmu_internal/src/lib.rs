@@ -1,6 +1,7 @@
 pub use kinmu_color as color;
 pub use kinmu_core as core;
 pub use kinmu_generator_with_annealing as generator_with_annealing;
+pub use kinmu_generator_with_solver as generator_with_solver;
 pub use kinmu_input_by_file as input_by_file;
 pub use kinmu_lib as lib;
 pub use kinmu_macros as macros;
@@ -472,6 +472,8 @@ mod tests {
                 attributes: vec![],
             }],
             ng_list: vec![],
+            pair_list: vec![],
+            availabilities: vec![],
             count: 1,
         };
         let dc = DayConfig {
@@ -481,6 +483,8 @@ mod tests {
             requested_schedule: Default::default(),
             schedule_states: Default::default(),
             attributes: HashMap::new(),
+            wishes: vec![],
+            shift_hours: vec![],
         };
         let props = (staff_count, day_count, sc, dc);
 
@@ -573,6 +577,8 @@ mod tests {
             attribute_map: Default::default(),
             list: Default::default(),
             ng_list: Default::default(),
+            pair_list: Default::default(),
+            availabilities: Default::default(),
             count: staff_count,
         };
         let dc = DayConfig {
@@ -582,6 +588,8 @@ mod tests {
             requested_schedule: Default::default(),
             schedule_states: Default::default(),
             attributes: HashMap::new(),
+            wishes: vec![],
+            shift_hours: vec![],
         };
 
         // Dayがbufferを除いて1-indexedになっている
@@ -618,6 +626,8 @@ mod tests {
             attribute_map: Default::default(),
             list: Default::default(),
             ng_list: Default::default(),
+            pair_list: Default::default(),
+            availabilities: Default::default(),
             count: staff_count,
         };
         let dc = DayConfig {
@@ -627,6 +637,8 @@ mod tests {
             requested_schedule: Default::default(),
             schedule_states: Default::default(),
             attributes: HashMap::new(),
+            wishes: vec![],
+            shift_hours: vec![],
         };
 
         let mut cw = CondWrapper::new(Cond::DayInList(vec![1, 3]));
@@ -657,6 +669,8 @@ mod tests {
             attribute_map: Default::default(),
             list: Default::default(),
             ng_list: Default::default(),
+            pair_list: Default::default(),
+            availabilities: Default::default(),
             count: staff_count,
         };
         let dc = DayConfig {
@@ -666,6 +680,8 @@ mod tests {
             requested_schedule: Default::default(),
             schedule_states: Default::default(),
             attributes: HashMap::new(),
+            wishes: vec![],
+            shift_hours: vec![],
         };
 
         let mut cw = CondWrapper::new(Cond::StaffID(0));
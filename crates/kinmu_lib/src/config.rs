@@ -40,3 +40,16 @@ impl Check<StdScoreProp, Shift, ShiftState, DayState> for DayAttributeNameWrappe
         }
     }
 }
+
+/// 勤務時間を用いるスコアで、day.shift_hoursが指定されているかを確認するためのWrapper
+pub(super) struct ShiftHoursWrapper;
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for ShiftHoursWrapper {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        if schedule_config.day.shift_hours.is_empty() {
+            Err(anyhow::anyhow!("day.shift_hoursが指定されていません"))
+        } else {
+            Ok(())
+        }
+    }
+}
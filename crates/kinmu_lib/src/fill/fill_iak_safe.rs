@@ -1,14 +1,18 @@
 //! fill_iak_safeのモジュール
 //!
 //! ### アルゴリズム
-//! 1. K,Iの数が希望で出された数を越えないかチェックする
-//! 2. Randomの場所をIAKのパターンで埋め、残りはNで埋める
-//! 3. 指定されたIと今埋まっているIの差分を計算
-//! 4. 余分なIをランダムに消す
-//! 5. 孤立したAを消す
-//! 6. 指定されたKと今埋まっているKの差分を計算
-//! 7. 不足したKをランダムに足す
-//! 8. 余分なKを孤立したものを優先にランダムに消す
+//! 1. OneOfの場所を入れてよいシフトで埋める Nを入れてよいならNを優先する
+//! 2. K,Iの数が希望で出された数を越えないかチェックする
+//! 3. Randomの場所をIAKのパターンで埋め、残りはNで埋める
+//! 4. 指定されたIと今埋まっているIの差分を計算
+//! 5. 余分なIをランダムに消す
+//! 6. 孤立したAを消す
+//! 7. 指定されたKと今埋まっているKの差分を計算
+//! 8. 不足したKをランダムに足す
+//! 9. 余分なKを孤立したものを優先にランダムに消す
+//!
+//! シフトを消したり足したりする際は、その枠に入れてよいシフトかを確認する
+//! 消したり足したりできる枠がない場合はエラーを返す
 
 use super::super::{Schedule, ScheduleConfig, Shift, ShiftState};
 
@@ -34,23 +38,31 @@ macro_rules! count_shift_row {
 }
 
 /// 指定したシフトをランダムにNに変える
+/// Nに変えられる枠がない場合はエラーを返す
 fn remove_random<R: Rng>(
     shift: Shift,
     schedule_config: &ScheduleConfig,
     new_schedule: &mut Schedule,
     r: usize,
     rng: &mut R,
-) {
+) -> anyhow::Result<()> {
     let mut is: Vec<usize> = Vec::new();
     for c in schedule_config.day.buffer_count..schedule_config.day.count {
         if new_schedule[r][c] == shift
-            && schedule_config.day.schedule_states[r][c] != ShiftState::Absolute
+            && schedule_config.day.schedule_states[r][c].can_place(&Shift::N)
         {
             is.push(c);
         }
     }
+    anyhow::ensure!(
+        !is.is_empty(),
+        "{}の{}を減らせる枠がありません",
+        schedule_config.staff.list[r].name,
+        shift
+    );
     let rnd = rng.gen_range(0..is.len());
     new_schedule[r][is[rnd]] = Shift::N;
+    Ok(())
 }
 
 /// 孤立したAをNで置き換える
@@ -60,7 +72,7 @@ fn remove_improper_a(schedule_config: &ScheduleConfig, new_schedule: &mut Schedu
     for c in schedule_config.day.buffer_count..schedule_config.day.count {
         if new_schedule[r][c] == Shift::A
             && new_schedule[r][c - 1] != Shift::I
-            && schedule_config.day.schedule_states[r][c] != ShiftState::Absolute
+            && schedule_config.day.schedule_states[r][c].can_place(&Shift::N)
         {
             new_schedule[r][c] = Shift::N;
         }
@@ -68,23 +80,31 @@ fn remove_improper_a(schedule_config: &ScheduleConfig, new_schedule: &mut Schedu
 }
 
 /// ランダムな場所に指定したシフトを追加する
+/// 追加できる枠がない場合はエラーを返す
 fn add_random<R: Rng>(
     shift: Shift,
     schedule_config: &ScheduleConfig,
     new_schedule: &mut Schedule,
     r: usize,
     rng: &mut R,
-) {
+) -> anyhow::Result<()> {
     let mut is: Vec<usize> = Vec::new();
     for c in schedule_config.day.buffer_count..schedule_config.day.count {
         if new_schedule[r][c] == Shift::N
-            && schedule_config.day.schedule_states[r][c] != ShiftState::Absolute
+            && schedule_config.day.schedule_states[r][c].can_place(&shift)
         {
             is.push(c);
         }
     }
+    anyhow::ensure!(
+        !is.is_empty(),
+        "{}の{}を増やせる枠がありません",
+        schedule_config.staff.list[r].name,
+        shift
+    );
     let rnd = rng.gen_range(0..is.len());
     new_schedule[r][is[rnd]] = shift;
+    Ok(())
 }
 
 /// 適切な夜勤の数で表を埋める
@@ -93,13 +113,29 @@ fn add_random<R: Rng>(
 /// Uになっている枠のみ埋める
 /// Absoluteなら埋めない
 /// schedule_configは夜勤の数(IDayCount)と公休の数(KDayCount)を持つ必要がある
-/// 夜勤か公休の数が自由度を超える場合、panicを起こす
+/// 夜勤か公休の数が自由度を超える場合、エラーを返す
 pub fn fill_iak_safe<R: Rng>(
     schedule_config: &ScheduleConfig,
     rng: &mut R,
 ) -> anyhow::Result<Schedule> {
     let mut schedule = schedule_config.day.requested_schedule.clone();
 
+    // OneOfの場所を埋める
+    // 埋めたシフトもK,Iの数に含めるため、数のチェックより先に行う
+    for r in 0..schedule_config.staff.count {
+        for c in schedule_config.day.buffer_count..schedule_config.day.count {
+            if let ShiftState::OneOf(shifts) = &schedule_config.day.schedule_states[r][c] {
+                if schedule[r][c] == Shift::U {
+                    schedule[r][c] = if shifts.contains(&Shift::N) {
+                        Shift::N
+                    } else {
+                        shifts[rng.gen_range(0..shifts.len())]
+                    };
+                }
+            }
+        }
+    }
+
     // Kの数が超過していないかチェック
     #[allow(clippy::needless_range_loop)]
     for r in 0..schedule_config.staff.count {
@@ -119,7 +155,6 @@ pub fn fill_iak_safe<R: Rng>(
     }
 
     // Iの数が超過していないかチェック
-    #[allow(clippy::needless_range_loop)]
     for r in 0..schedule_config.staff.count {
         let c1 = count_shift_row!(Shift::I, schedule_config, schedule, r);
         let c2 = schedule_config
@@ -182,7 +217,7 @@ pub fn fill_iak_safe<R: Rng>(
 
         // 余分なIをランダムに消す
         for _ in 0..i_dif {
-            remove_random(Shift::I, schedule_config, &mut schedule, r, rng);
+            remove_random(Shift::I, schedule_config, &mut schedule, r, rng)?;
         }
 
         // 孤立したAを消す
@@ -197,7 +232,7 @@ pub fn fill_iak_safe<R: Rng>(
         if k_dif > 0 {
             // 不足したKをランダムに足す
             for _ in 0..k_dif {
-                add_random(Shift::K, schedule_config, &mut schedule, r, rng);
+                add_random(Shift::K, schedule_config, &mut schedule, r, rng)?;
             }
         } else {
             // 孤立したKとそうでないKのインデックスをとる
@@ -205,7 +240,7 @@ pub fn fill_iak_safe<R: Rng>(
             let mut k_ng_ids = Vec::new();
             for c in schedule_config.day.buffer_count..schedule_config.day.count {
                 if (schedule[r][c] == Shift::K)
-                    && (schedule_config.day.schedule_states[r][c] == ShiftState::Random)
+                    && schedule_config.day.schedule_states[r][c].can_place(&Shift::N)
                 {
                     if schedule[r][c - 1] == Shift::A {
                         k_ng_ids.push(c);
@@ -217,6 +252,12 @@ pub fn fill_iak_safe<R: Rng>(
 
             // Kを消す
             for _ in 0..-k_dif {
+                anyhow::ensure!(
+                    !k_nc_ids.is_empty() || !k_ng_ids.is_empty(),
+                    "{}の{}を減らせる枠がありません",
+                    schedule_config.staff.list[r].name,
+                    Shift::K
+                );
                 if k_nc_ids.is_empty() {
                     let rnd = rng.gen_range(0..k_ng_ids.len());
                     schedule[r][k_ng_ids[rnd]] = Shift::N;
@@ -231,3 +272,46 @@ pub fn fill_iak_safe<R: Rng>(
     }
     Ok(schedule)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    /// 職員1人で、KDayCountとIDayCountを指定したScheduleConfigを作る
+    fn config(k_count: i32, i_count: i32, states: Vec<ShiftState>) -> ScheduleConfig {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.staff.count = 1;
+        schedule_config.staff.attribute_map.names =
+            vec!["KDayCount".to_string(), "IDayCount".to_string()];
+        schedule_config.staff.attribute_map.name_to_index =
+            [("KDayCount".to_string(), 0), ("IDayCount".to_string(), 1)]
+                .into_iter()
+                .collect();
+        schedule_config.staff.list = vec![kinmu_model::Staff {
+            name: "職員A".to_string(),
+            attributes: vec![k_count, i_count],
+        }];
+        schedule_config.day.count = states.len();
+        schedule_config.day.requested_schedule = vec![vec![Shift::U; states.len()]];
+        schedule_config.day.schedule_states = vec![states];
+        schedule_config
+    }
+
+    /// OneOfで埋めたKがKDayCountを超える場合、エラーを返すケース
+    #[test]
+    fn test_one_of_counted() {
+        let schedule_config = config(0, 0, vec![ShiftState::OneOf(vec![Shift::K]); 2]);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        assert!(fill_iak_safe(&schedule_config, &mut rng).is_err());
+    }
+
+    /// Kを入れられる枠がない場合、panicせずエラーを返すケース
+    #[test]
+    fn test_no_candidate() {
+        let schedule_config = config(1, 0, vec![ShiftState::OneOf(vec![Shift::N, Shift::I]); 3]);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        assert!(fill_iak_safe(&schedule_config, &mut rng).is_err());
+    }
+}
@@ -8,6 +8,7 @@ fill2はIとKの数合わせてうまいこと埋める
 use super::{DayState, Schedule, ScheduleConfig, Shift, ShiftState, StdScoreProp};
 
 use kinmu_generator_with_annealing::Fill;
+use kinmu_input_by_file::{unknown_function_error, CheckFunction};
 
 use rand::Rng;
 
@@ -19,6 +20,9 @@ use fill_iak_safe::fill_iak_safe;
 use fill_noh::fill_noh;
 use no_fill::no_fill;
 
+/// 登録されているfill関数の名前
+const FILL_NAMES: [&str; 3] = ["no_fill", "fill_noh", "fill_iak_safe"];
+
 /// 生成器で用いるFill関数のための型
 /// GeneratorWithAnnealingのFillを実装
 #[derive(Debug, Clone)]
@@ -36,7 +40,18 @@ impl Fill<StdScoreProp, Shift, ShiftState, DayState> for StdFill {
             "no_fill" => Ok(no_fill(schedule_config, &mut rng)),
             "fill_noh" => Ok(fill_noh(schedule_config, &mut rng)),
             "fill_iak_safe" => fill_iak_safe(schedule_config, &mut rng),
-            _ => Err(anyhow::anyhow!("Failed to parse fill function {}", name)),
+            _ => Err(unknown_function_error(name, &FILL_NAMES)),
+        }
+    }
+}
+
+/// fill関数の名前が登録されているか判定
+impl CheckFunction<StdScoreProp, Shift, ShiftState, DayState> for StdFill {
+    fn check_function(&self, name: &str, _schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        if FILL_NAMES.contains(&name) {
+            Ok(())
+        } else {
+            Err(unknown_function_error(name, &FILL_NAMES))
         }
     }
 }
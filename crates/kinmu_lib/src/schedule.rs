@@ -1,5 +1,6 @@
 //! 勤務表に関わる型の宣言
 
+use kinmu_generator_with_solver::Candidates;
 use kinmu_input_by_file::{FromConfig, MapState};
 use kinmu_output_html::ToJapanese;
 
@@ -74,11 +75,41 @@ impl FromConfig for Shift {
 pub type Schedule = kinmu_model::Schedule<Shift>;
 
 /// シフトが固定かどうかを管理する型
+/// OneOfは指定したシフトのいずれかしか入れられないことを表す
+/// requested_scheduleで[NK]のように指定した枠や、availabilityで制限した未定の枠に用いる
 #[derive(PartialEq, Clone, Debug, Default)]
 pub enum ShiftState {
     #[default]
     Absolute,
     Random,
+    OneOf(Vec<Shift>),
+}
+
+impl ShiftState {
+    /// この枠に指定したシフトを入れてよいか
+    /// Absoluteの枠には何も入れられない
+    pub fn can_place(&self, shift: &Shift) -> bool {
+        match self {
+            ShiftState::Absolute => false,
+            ShiftState::Random => true,
+            ShiftState::OneOf(shifts) => shifts.contains(shift),
+        }
+    }
+}
+
+/// ソルバーでAbsoluteでない枠に入れてよいシフト
+const SOLVER_SHIFTS: [Shift; 6] = [Shift::N, Shift::K, Shift::I, Shift::A, Shift::O, Shift::H];
+
+impl Candidates<Shift> for ShiftState {
+    /// Absoluteの枠は希望された表のシフトのみ
+    /// Randomの枠はN,K,I,A,O,Hのいずれか
+    fn candidates(&self, requested: &Shift) -> Vec<Shift> {
+        match self {
+            ShiftState::Absolute => vec![*requested],
+            ShiftState::Random => SOLVER_SHIFTS.to_vec(),
+            ShiftState::OneOf(shifts) => shifts.clone(),
+        }
+    }
 }
 
 /// ShiftStateを用いる具体的なScheduleState
@@ -86,12 +117,33 @@ pub type ScheduleState = kinmu_model::ScheduleState<ShiftState>;
 
 impl MapState<ShiftState> for Shift {
     const BUFFER_CASE: ShiftState = ShiftState::Absolute;
+    const FREE_CASE: Shift = Shift::U;
     fn to_state(&self) -> ShiftState {
         match self {
             Shift::U => ShiftState::Random,
             _ => ShiftState::Absolute,
         }
     }
+    fn to_restricted_state(&self, shifts: &[Self]) -> ShiftState {
+        match self {
+            Shift::U => ShiftState::OneOf(shifts.to_vec()),
+            _ => ShiftState::Absolute,
+        }
+    }
+    fn restrict_state(&self, state: &ShiftState, shifts: &[Self]) -> Option<ShiftState> {
+        match state {
+            ShiftState::Absolute => shifts.contains(self).then_some(ShiftState::Absolute),
+            ShiftState::Random => Some(self.to_restricted_state(shifts)),
+            ShiftState::OneOf(current) => {
+                let common = current
+                    .iter()
+                    .filter(|s| shifts.contains(s))
+                    .copied()
+                    .collect::<Vec<_>>();
+                (!common.is_empty()).then_some(ShiftState::OneOf(common))
+            }
+        }
+    }
 }
 
 /// 曜日を管理する型
@@ -141,3 +193,64 @@ impl ToJapanese for DayState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 固定された枠は、そのシフトが指定したシフトに含まれる場合のみ制限できるケース
+    #[test]
+    fn test_restrict_absolute() {
+        assert_eq!(
+            Shift::K.restrict_state(&ShiftState::Absolute, &[Shift::K, Shift::Y]),
+            Some(ShiftState::Absolute)
+        );
+        assert_eq!(
+            Shift::N.restrict_state(&ShiftState::Absolute, &[Shift::K, Shift::Y]),
+            None
+        );
+    }
+
+    /// 未定の枠は指定したシフトのOneOfに、固定された枠はAbsoluteになるケース
+    #[test]
+    fn test_to_restricted_state() {
+        assert_eq!(
+            Shift::U.to_restricted_state(&[Shift::N, Shift::K]),
+            ShiftState::OneOf(vec![Shift::N, Shift::K])
+        );
+        assert_eq!(
+            Shift::N.to_restricted_state(&[Shift::N, Shift::K]),
+            ShiftState::Absolute
+        );
+    }
+
+    /// Randomの枠を制限すると、指定したシフトのOneOfになるケース
+    #[test]
+    fn test_restrict_random() {
+        assert_eq!(
+            Shift::U.restrict_state(&ShiftState::Random, &[Shift::K, Shift::Y]),
+            Some(ShiftState::OneOf(vec![Shift::K, Shift::Y]))
+        );
+    }
+
+    /// OneOfの枠を制限すると、共通するシフトのみのOneOfになるケース
+    #[test]
+    fn test_restrict_one_of() {
+        assert_eq!(
+            Shift::U.restrict_state(
+                &ShiftState::OneOf(vec![Shift::N, Shift::K]),
+                &[Shift::K, Shift::Y]
+            ),
+            Some(ShiftState::OneOf(vec![Shift::K]))
+        );
+    }
+
+    /// OneOfの枠と共通するシフトがない場合、Noneを返すケース
+    #[test]
+    fn test_restrict_one_of_empty() {
+        assert_eq!(
+            Shift::U.restrict_state(&ShiftState::OneOf(vec![Shift::N, Shift::K]), &[Shift::Y]),
+            None
+        );
+    }
+}
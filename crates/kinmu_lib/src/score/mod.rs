@@ -2,47 +2,68 @@
 
 mod ng_pair;
 mod no_same_pair;
+mod pair_count_at_least;
 mod pattern_fixed;
 mod pattern_fixed_any;
 mod pattern_general;
 mod pattern_general_any;
+mod rest_block_count_at_least;
+mod run_length;
 mod shift_count_regard_staff_attribute;
 mod shift_dir_priority;
 mod shift_distance;
 mod shift_half_balance;
 mod shifts_balance;
 mod shifts_count_at_most;
+mod shifts_weekly_at_most_regard_availability;
 mod staff_count;
 mod staff_count_at_least;
+mod staff_count_in_range;
 mod staff_count_regard_day_attribute;
 mod staff_count_variance;
 mod staff_count_with_premise;
 mod streak;
+mod unmet_wish;
+mod weekend_off_count_at_least;
+mod wish_satisfaction_variance;
+mod working_hours_regard_staff_attribute;
+mod working_hours_weekly_at_most;
 
 use self::ng_pair::NGPair;
 use self::no_same_pair::NoSamePair;
+use self::pair_count_at_least::PairCountAtLeast;
 use self::pattern_fixed::PatternFixed;
 use self::pattern_fixed_any::PatternFixedAny;
 use self::pattern_general::PatternGeneral;
 use self::pattern_general_any::PatternGeneralAny;
+use self::rest_block_count_at_least::RestBlockCountAtLeast;
+use self::run_length::RunLength;
 use self::shift_count_regard_staff_attribute::ShiftCountRegardStaffAttribute;
 use self::shift_dir_priority::ShiftDirPriority;
 use self::shift_distance::ShiftDistance;
 use self::shift_half_balance::ShiftHalfBalance;
 use self::shifts_balance::ShiftsBalance;
 use self::shifts_count_at_most::ShiftsCountAtMost;
+use self::shifts_weekly_at_most_regard_availability::ShiftsWeeklyAtMostRegardAvailability;
 use self::staff_count::StaffCount;
 use self::staff_count_at_least::StaffCountAtLeast;
+use self::staff_count_in_range::StaffCountInRange;
 use self::staff_count_regard_day_attribute::StaffCountRegardDayAttribute;
 use self::staff_count_variance::StaffCountVariance;
 use self::staff_count_with_premise::StaffCountWithPremise;
 use self::streak::Streak;
+use self::unmet_wish::UnmetWish;
+use self::weekend_off_count_at_least::WeekendOffCountAtLeast;
+use self::wish_satisfaction_variance::WishSatisfactionVariance;
+use self::working_hours_regard_staff_attribute::WorkingHoursRegardStaffAttribute;
+use self::working_hours_weekly_at_most::WorkingHoursWeeklyAtMost;
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState,
-    StaffAttributeNameWrapper,
+    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftHoursWrapper,
+    ShiftState, StaffAttributeNameWrapper,
 };
 
+use kinmu_generator_with_solver::{Constraint, ToConstraints};
 use kinmu_input_by_file::{Check, FromConfig, VecVecWrapper, VecWrapper};
 use kinmu_macros::ScoreProp;
 use kinmu_model::{DayAttributeName, Score, StaffAttributeName, StaffConfig};
@@ -107,6 +128,36 @@ pub enum StdScoreProp {
 
     /// 指定したシフトで同じペアが指定回数以上ある場合のペナルティを指定
     NoSamePair(NoSamePair),
+
+    /// 職員ごとの週あたりの勤務時間が指定したパラメータを超えることによるペナルティを指定
+    WorkingHoursWeeklyAtMost(WorkingHoursWeeklyAtMost),
+
+    /// 職員ごとの指定したパラメータと勤務時間の差が許容幅を超えることによるペナルティを指定
+    WorkingHoursRegardStaffAttribute(WorkingHoursRegardStaffAttribute),
+
+    /// 指定したシフトが指定日数以上連続するまとまりの数が指定した数に達しない場合のペナルティを指定
+    RestBlockCountAtLeast(RestBlockCountAtLeast),
+
+    /// Condを満たす連続した日付を全て指定したシフトで過ごせた回数が指定した数に達しない場合のペナルティを指定
+    WeekendOffCountAtLeast(WeekendOffCountAtLeast),
+
+    /// 指定したシフトが連続する長さが指定した範囲に収まらない場合に、範囲からのずれに比例したペナルティを指定
+    RunLength(RunLength),
+
+    /// 指定したシフトの人数が指定した範囲に収まらない場合に、範囲からのずれの2乗に比例したペナルティを指定
+    StaffCountInRange(StaffCountInRange),
+
+    /// ペアリストにあるペアがともに指定したシフトになる日数が指定した数より少ない場合に、不足分の2乗に比例したペナルティを指定
+    PairCountAtLeast(PairCountAtLeast),
+
+    /// day.wishesで指定した希望が叶わなかった場合に、その優先度に比例したペナルティを指定
+    UnmetWish(UnmetWish),
+
+    /// 職員ごとの希望が叶った割合の分散に比例したペナルティを指定
+    WishSatisfactionVariance(WishSatisfactionVariance),
+
+    /// 週ごとの指定したシフトの数がstaff.availabilityで指定した上限を超える場合のペナルティを指定
+    ShiftsWeeklyAtMostRegardAvailability(ShiftsWeeklyAtMostRegardAvailability),
 }
 
 impl Default for StdScoreProp {
@@ -144,6 +195,36 @@ impl fmt::Display for StdScoreProp {
             StdScoreProp::StaffCountVariance(p) => write!(f, "StaffCountVariance {:?}", p),
             StdScoreProp::NGPair(p) => write!(f, "NGPair {:?}", p),
             StdScoreProp::NoSamePair(p) => write!(f, "NoSamePair {:?}", p),
+            StdScoreProp::WorkingHoursWeeklyAtMost(p) => {
+                write!(f, "WorkingHoursWeeklyAtMost {:?}", p)
+            }
+            StdScoreProp::WorkingHoursRegardStaffAttribute(p) => {
+                write!(f, "WorkingHoursRegardStaffAttribute {:?}", p)
+            }
+            StdScoreProp::RestBlockCountAtLeast(p) => write!(f, "RestBlockCountAtLeast {:?}", p),
+            StdScoreProp::WeekendOffCountAtLeast(p) => write!(f, "WeekendOffCountAtLeast {:?}", p),
+            StdScoreProp::RunLength(p) => write!(f, "RunLength {:?}", p),
+            StdScoreProp::StaffCountInRange(p) => write!(f, "StaffCountInRange {:?}", p),
+            StdScoreProp::PairCountAtLeast(p) => write!(f, "PairCountAtLeast {:?}", p),
+            StdScoreProp::UnmetWish(p) => write!(f, "UnmetWish {:?}", p),
+            StdScoreProp::WishSatisfactionVariance(p) => {
+                write!(f, "WishSatisfactionVariance {:?}", p)
+            }
+            StdScoreProp::ShiftsWeeklyAtMostRegardAvailability(p) => {
+                write!(f, "ShiftsWeeklyAtMostRegardAvailability {:?}", p)
+            }
+        }
+    }
+}
+
+impl ToConstraints<StdScoreProp, Shift, ShiftState, DayState> for StdScoreProp {
+    fn to_constraints(&self, schedule_config: &ScheduleConfig) -> Option<Vec<Constraint<Shift>>> {
+        match self {
+            StdScoreProp::PatternFixed(p) => p.to_constraints(schedule_config),
+            StdScoreProp::StaffCount(p) => p.to_constraints(schedule_config),
+            StdScoreProp::StaffCountAtLeast(p) => p.to_constraints(schedule_config),
+            StdScoreProp::NGPair(p) => p.to_constraints(schedule_config),
+            _ => None,
         }
     }
 }
@@ -169,6 +250,16 @@ impl Check<StdScoreProp, Shift, ShiftState, DayState> for StdScoreProp {
             StdScoreProp::StaffCountVariance(p) => p.check(schedule_config),
             StdScoreProp::NGPair(p) => p.check(schedule_config),
             StdScoreProp::NoSamePair(p) => p.check(schedule_config),
+            StdScoreProp::WorkingHoursWeeklyAtMost(p) => p.check(schedule_config),
+            StdScoreProp::WorkingHoursRegardStaffAttribute(p) => p.check(schedule_config),
+            StdScoreProp::RestBlockCountAtLeast(p) => p.check(schedule_config),
+            StdScoreProp::WeekendOffCountAtLeast(p) => p.check(schedule_config),
+            StdScoreProp::RunLength(p) => p.check(schedule_config),
+            StdScoreProp::StaffCountInRange(p) => p.check(schedule_config),
+            StdScoreProp::PairCountAtLeast(p) => p.check(schedule_config),
+            StdScoreProp::UnmetWish(p) => p.check(schedule_config),
+            StdScoreProp::WishSatisfactionVariance(p) => p.check(schedule_config),
+            StdScoreProp::ShiftsWeeklyAtMostRegardAvailability(p) => p.check(schedule_config),
         }
         .with_context(|| format!("スコア {:?} の変換チェックに失敗しました", self))?;
 
@@ -275,16 +366,216 @@ fn helper_sp(w1: &str, w2: &str) -> anyhow::Result<StdScoreProp> {
         )>::from_config(
             p
         )?))),
+        ("WorkingHoursWeeklyAtMost", p) => Ok(StdScoreProp::WorkingHoursWeeklyAtMost(
+            WorkingHoursWeeklyAtMost::new(<(
+                CondWrapper,
+                DayAttributeName,
+                StaffAttributeName,
+                Score,
+            )>::from_config(p)?),
+        )),
+        ("WorkingHoursRegardStaffAttribute", p) => Ok(
+            StdScoreProp::WorkingHoursRegardStaffAttribute(WorkingHoursRegardStaffAttribute::new(
+                <(CondWrapper, StaffAttributeName, f32, Score)>::from_config(p)?,
+            )),
+        ),
+        ("RestBlockCountAtLeast", p) => Ok(StdScoreProp::RestBlockCountAtLeast({
+            let (cw, VecWrapper(vs), l, i, s) =
+                <(CondWrapper, VecWrapper<Shift>, i32, i32, Score)>::from_config(p)?;
+            RestBlockCountAtLeast::new((cw, vs, l, i, s))
+        })),
+        ("WeekendOffCountAtLeast", p) => Ok(StdScoreProp::WeekendOffCountAtLeast({
+            let (cw, VecWrapper(vs), i, s) =
+                <(CondWrapper, VecWrapper<Shift>, i32, Score)>::from_config(p)?;
+            WeekendOffCountAtLeast::new((cw, vs, i, s))
+        })),
+        ("RunLength", p) => Ok(StdScoreProp::RunLength({
+            let (cw, VecWrapper(vs), min, max, s) =
+                <(CondWrapper, VecWrapper<Shift>, i32, i32, Score)>::from_config(p)?;
+            RunLength::new((cw, vs, min, max, s))
+        })),
+        ("StaffCountInRange", p) => Ok(StdScoreProp::StaffCountInRange(StaffCountInRange::new(
+            <(CondWrapper, Shift, i32, i32, Score)>::from_config(p)?,
+        ))),
+        ("PairCountAtLeast", p) => Ok(StdScoreProp::PairCountAtLeast(PairCountAtLeast::new(
+            <(CondWrapper, Shift, i32, Score)>::from_config(p)?,
+        ))),
+        ("UnmetWish", p) => Ok(StdScoreProp::UnmetWish(UnmetWish::new(<(
+            CondWrapper,
+            Score,
+        )>::from_config(
+            p
+        )?))),
+        ("WishSatisfactionVariance", p) => Ok(StdScoreProp::WishSatisfactionVariance(
+            WishSatisfactionVariance::new(<(CondWrapper, Score)>::from_config(p)?),
+        )),
+        ("ShiftsWeeklyAtMostRegardAvailability", p) => {
+            Ok(StdScoreProp::ShiftsWeeklyAtMostRegardAvailability({
+                let (cw, VecWrapper(vs), w, s) =
+                    <(CondWrapper, VecWrapper<Shift>, DayAttributeName, Score)>::from_config(p)?;
+                ShiftsWeeklyAtMostRegardAvailability::new((cw, vs, w, s))
+            }))
+        }
         (s, _) => Err(anyhow::anyhow!("Unexpected ScoreProp {}", s)),
     }
 }
 
+/// 枠に指定したシフトが必ず入るか、入りうるかを返す
+/// Absoluteの枠は希望された表のシフトで、それ以外は入れてよいシフトかで判定する
+fn shift_fixed_or_placeable(
+    day_config: &DayConfig,
+    staff: usize,
+    day: usize,
+    shift: Shift,
+) -> (bool, bool) {
+    match &day_config.schedule_states[staff][day] {
+        ShiftState::Absolute => {
+            let fixed = day_config.requested_schedule[staff][day] == shift;
+            (fixed, fixed)
+        }
+        state => (false, state.can_place(&shift)),
+    }
+}
+
+/// ある日の条件に合う職員のうち、指定したシフトが入りうる人数の範囲
+struct StaffCountRange {
+    fixed: i32,     // 希望された表で固定されている人数
+    placeable: i32, // 入れられる人数の最大
+}
+
+/// 指定した日の、条件に合う職員のシフトの人数の範囲を求める
+/// 条件によりスキップされる日や条件に合う職員がいない日はNone
+fn staff_count_range(
+    staff_config: &StaffConfig,
+    day_config: &DayConfig,
+    cond: &CondWrapper,
+    shift: Shift,
+    day: usize,
+) -> Option<StaffCountRange> {
+    if cond.can_skip_day_immut(day, staff_config, day_config) {
+        return None;
+    }
+    let mut is_valid = false;
+    let mut range = StaffCountRange {
+        fixed: 0,
+        placeable: 0,
+    };
+    for staff in 0..staff_config.count {
+        if !cond.eval_immut(staff, day, staff_config, day_config) {
+            continue;
+        }
+        is_valid = true;
+        let (fixed, placeable) = shift_fixed_or_placeable(day_config, staff, day, shift);
+        range.fixed += fixed as i32;
+        range.placeable += placeable as i32;
+    }
+    if is_valid {
+        Some(range)
+    } else {
+        None
+    }
+}
+
+/// 条件に合う日ごとに、シフトの人数が必要な範囲に収まりうるか判定し、
+/// 収まらない日のメッセージを返す
+/// rangeは日ごとの最小の人数と最大の人数を返す
+/// scoreが正でない場合は人数が外れてもペナルティにならないため判定しない
+fn diagnose_staff_count<F>(
+    staff_config: &StaffConfig,
+    day_config: &DayConfig,
+    cond: &CondWrapper,
+    shift: Shift,
+    score: Score,
+    range: F,
+) -> Vec<String>
+where
+    F: Fn(usize) -> (i32, Option<i32>),
+{
+    let mut messages = Vec::new();
+    if score <= 0.0 {
+        return messages;
+    }
+    for day in 0..day_config.count {
+        let count = match staff_count_range(staff_config, day_config, cond, shift, day) {
+            Some(count) => count,
+            None => continue,
+        };
+        let (min, max) = range(day);
+        let display_day = (day + 1).saturating_sub(day_config.buffer_count);
+        if count.placeable < min {
+            messages.push(format!(
+                "{}日目: {}が{}人必要ですが、入れられる職員は{}人です",
+                display_day, shift, min, count.placeable
+            ));
+        }
+        if let Some(max) = max {
+            if max < count.fixed {
+                messages.push(format!(
+                    "{}日目: {}は{}人以下の必要がありますが、希望された表で{}人に固定されています",
+                    display_day, shift, max, count.fixed
+                ));
+            }
+        }
+    }
+    messages
+}
+
+/// 0以上の値にscoreをかけるスコアの下限を見積もる
+/// scoreが負の場合は見積もれないため、負の無限大とする
+fn penalty_lower_bound(score: Score) -> Score {
+    if 0.0 <= score {
+        0.0
+    } else {
+        Score::NEG_INFINITY
+    }
+}
+
+/// 人数が範囲から外れた分の2乗にscoreをかけるスコアの下限を見積もる
+/// 日ごとに、入りうる人数の範囲のうちペナルティが最小になる人数を選んで和をとる
+/// scoreが負の場合は人数の範囲の端が最小になる
+fn staff_count_lower_bound<F>(
+    staff_config: &StaffConfig,
+    day_config: &DayConfig,
+    cond: &CondWrapper,
+    shift: Shift,
+    score: Score,
+    range: F,
+) -> Score
+where
+    F: Fn(usize) -> (i32, Option<i32>),
+{
+    let mut sum = 0.0;
+    for day in 0..day_config.count {
+        let count = match staff_count_range(staff_config, day_config, cond, shift, day) {
+            Some(count) => count,
+            None => continue,
+        };
+        let (min, max) = range(day);
+        let penalty = |c: i32| {
+            let d = (std::cmp::max(min - c, 0) + max.map_or(0, |max| std::cmp::max(c - max, 0)))
+                as Score;
+            d * d * score
+        };
+        sum += [
+            count.fixed,
+            count.placeable,
+            min.clamp(count.fixed, count.placeable),
+        ]
+        .into_iter()
+        .map(penalty)
+        .fold(Score::INFINITY, Score::min);
+    }
+    sum
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use super::super::Cond;
 
+    use kinmu_model::lower_bound_scores;
+
     #[test]
     fn vec_shift_test() {
         let v1 = <VecWrapper<Shift>>::from_config("[N, I, K]").unwrap();
@@ -322,4 +613,44 @@ mod tests {
             StdScoreProp::from_config(s).unwrap()
         );
     }
+
+    #[test]
+    fn working_hours_test() {
+        let s = "WorkingHoursWeeklyAtMost (NoBuffer (), 週, 週上限, 10)";
+        assert_eq!(
+            StdScoreProp::WorkingHoursWeeklyAtMost(WorkingHoursWeeklyAtMost::new((
+                CondWrapper::new(Cond::NoBuffer),
+                String::from("週"),
+                String::from("週上限"),
+                10.0
+            ))),
+            StdScoreProp::from_config(s).unwrap()
+        );
+    }
+
+    /// 負のスコアを含む場合、下限は見積もれないケース
+    #[test]
+    fn test_penalty_lower_bound() {
+        let schedule_config: ScheduleConfig = Default::default();
+        let streak = |score| {
+            StdScoreProp::Streak(Streak::new((
+                CondWrapper::new(Cond::True),
+                vec![Shift::N],
+                2,
+                score,
+            )))
+        };
+
+        let sps = vec![streak(1.0), streak(2.0)];
+        assert_eq!(
+            0.0,
+            lower_bound_scores(&sps, &schedule_config.staff, &schedule_config.day)
+        );
+
+        let sps = vec![streak(1.0), streak(-100.0)];
+        assert_eq!(
+            Score::NEG_INFINITY,
+            lower_bound_scores(&sps, &schedule_config.staff, &schedule_config.day)
+        );
+    }
 }
@@ -1,10 +1,11 @@
 //! NGリストにあるペアがともに指定したシフトなら発火するスコア
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
 };
 
+use kinmu_generator_with_solver::{Constraint, ToConstraints};
 use kinmu_input_by_file::Check;
 use kinmu_model::{Score, ScoreProp};
 
@@ -77,6 +78,10 @@ impl ScoreProp<Shift, ShiftState, DayState> for NGPair {
             schedule
         )
     }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for NGPair {
@@ -85,6 +90,33 @@ impl Check<StdScoreProp, Shift, ShiftState, DayState> for NGPair {
     }
 }
 
+impl ToConstraints<StdScoreProp, Shift, ShiftState, DayState> for NGPair {
+    /// 条件に合う日ごとに、NGな職員の組が同時にシフトに入らない制約に変換する
+    fn to_constraints(&self, schedule_config: &ScheduleConfig) -> Option<Vec<Constraint<Shift>>> {
+        if self.score <= 0.0 {
+            return None;
+        }
+        let staff_config = &schedule_config.staff;
+        let day_config = &schedule_config.day;
+        let mut constraints = Vec::new();
+        for day in 0..day_config.count {
+            if self.cond.can_skip_day_immut(day, staff_config, day_config) {
+                continue;
+            }
+            for &(staff1, staff2) in &staff_config.ng_list {
+                if self.cond.eval_immut(staff1, day, staff_config, day_config)
+                    && self.cond.eval_immut(staff2, day, staff_config, day_config)
+                {
+                    constraints.push(Constraint::Forbid {
+                        literals: vec![((staff1, day), self.shift), ((staff2, day), self.shift)],
+                    });
+                }
+            }
+        }
+        Some(constraints)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Cond;
@@ -132,4 +164,26 @@ mod tests {
 
         assert_eq!(2.0, score);
     }
+
+    /// 日ごとに、NGな職員の組が同時にシフトに入らない制約になるケース
+    #[test]
+    fn test_to_constraints() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = 2;
+        schedule_config.staff.count = 3;
+        schedule_config.staff.ng_list.push((0, 2));
+
+        let sp = NGPair::new((CondWrapper::new(Cond::True), Shift::I, 1.0));
+        assert_eq!(
+            sp.to_constraints(&schedule_config),
+            Some(vec![
+                Constraint::Forbid {
+                    literals: vec![((0, 0), Shift::I), ((2, 0), Shift::I)]
+                },
+                Constraint::Forbid {
+                    literals: vec![((0, 1), Shift::I), ((2, 1), Shift::I)]
+                },
+            ])
+        );
+    }
 }
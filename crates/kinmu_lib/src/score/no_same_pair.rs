@@ -1,8 +1,8 @@
 //! 指定回数以上同じペアなら発火するスコア
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
 };
 
 use kinmu_input_by_file::Check;
@@ -92,6 +92,10 @@ impl ScoreProp<Shift, ShiftState, DayState> for NoSamePair {
             schedule
         )
     }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for NoSamePair {
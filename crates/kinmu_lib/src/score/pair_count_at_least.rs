@@ -0,0 +1,135 @@
+//! ペアリストにあるペアがともに指定したシフトになる日数が、指定した数に達しない場合に発火するスコア
+//! 新人とプリセプターを一緒に勤務させるときなどに用いる
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp};
+
+macro_rules! eval {
+    ($eval:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        for i in 0..$staff_config.pair_list.len() {
+            let (staff1, staff2) = $staff_config.pair_list[i];
+            let mut is_valid = false;
+            let mut pair_count = 0;
+            for day in 0..$day_config.count {
+                if $self.cond.$eval(staff1, day, $staff_config, $day_config)
+                    && $self.cond.$eval(staff2, day, $staff_config, $day_config)
+                {
+                    is_valid = true;
+                    if $schedule[staff1][day] == $self.shift
+                        && $schedule[staff2][day] == $self.shift
+                    {
+                        pair_count += 1;
+                    }
+                }
+            }
+            if is_valid {
+                let d = std::cmp::min(pair_count - $self.count, 0) as Score;
+                sum += d * d;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PairCountAtLeast {
+    pub cond: CondWrapper,
+    pub shift: Shift,
+    pub count: i32,
+    pub score: Score,
+}
+
+impl PairCountAtLeast {
+    pub fn new((cond, shift, count, score): (CondWrapper, Shift, i32, Score)) -> Self {
+        Self {
+            cond,
+            shift,
+            count,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for PairCountAtLeast {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(eval_mut, self, staff_config, day_config, schedule)
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(eval_immut, self, staff_config, day_config, schedule)
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for PairCountAtLeast {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond.check(schedule_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// ペアが2日ともにNになっているケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, K, N], vec![N, K, N, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.staff.pair_list.push((0, 1));
+
+        let mut sp = PairCountAtLeast::new((CondWrapper::new(Cond::True), Shift::N, 2, 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// ペアがともにNになっている日が1日もないケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, K, K], vec![K, K, N, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.staff.pair_list.push((0, 1));
+
+        let mut sp = PairCountAtLeast::new((CondWrapper::new(Cond::True), Shift::N, 2, 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(4.0, score);
+    }
+}
@@ -3,10 +3,11 @@
 //! TODO: RollingHash、FSMやTrie木を用いた高速化
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
 };
 
+use kinmu_generator_with_solver::{Constraint, ToConstraints};
 use kinmu_input_by_file::Check;
 use kinmu_model::{Score, ScoreProp};
 
@@ -101,6 +102,10 @@ impl ScoreProp<Shift, ShiftState, DayState> for PatternFixed {
             schedule
         )
     }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for PatternFixed {
@@ -109,6 +114,46 @@ impl Check<StdScoreProp, Shift, ShiftState, DayState> for PatternFixed {
     }
 }
 
+impl ToConstraints<StdScoreProp, Shift, ShiftState, DayState> for PatternFixed {
+    /// 条件に合う範囲ごとに、パターンが出現しない制約に変換する
+    fn to_constraints(&self, schedule_config: &ScheduleConfig) -> Option<Vec<Constraint<Shift>>> {
+        if self.score <= 0.0 {
+            return None;
+        }
+        let staff_config = &schedule_config.staff;
+        let day_config = &schedule_config.day;
+        let mut constraints = Vec::new();
+        for staff in 0..staff_config.count {
+            if self
+                .cond
+                .can_skip_staff_immut(staff, staff_config, day_config)
+            {
+                continue;
+            }
+            for day in 0..day_config.count {
+                let valid = !self.shift_pattern.is_empty()
+                    && (0..self.shift_pattern.len()).all(|dd| {
+                        day + dd < day_config.count
+                            && self
+                                .cond
+                                .eval_immut(staff, day + dd, staff_config, day_config)
+                    });
+                if valid {
+                    constraints.push(Constraint::Forbid {
+                        literals: self
+                            .shift_pattern
+                            .iter()
+                            .enumerate()
+                            .map(|(dd, s)| ((staff, day + dd), *s))
+                            .collect(),
+                    });
+                }
+            }
+        }
+        Some(constraints)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Cond;
@@ -175,4 +220,28 @@ mod tests {
 
         assert_eq!(2.0, score);
     }
+
+    /// スコアが正の場合のみ、パターンが入りうる範囲ごとに出現しない制約になるケース
+    #[test]
+    fn test_to_constraints() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = 3;
+        schedule_config.staff.count = 1;
+
+        let sp = PatternFixed::new((CondWrapper::new(Cond::True), vec![Shift::O, Shift::H], 1.0));
+        assert_eq!(
+            sp.to_constraints(&schedule_config),
+            Some(vec![
+                Constraint::Forbid {
+                    literals: vec![((0, 0), Shift::O), ((0, 1), Shift::H)]
+                },
+                Constraint::Forbid {
+                    literals: vec![((0, 1), Shift::O), ((0, 2), Shift::H)]
+                },
+            ])
+        );
+
+        let sp = PatternFixed::new((CondWrapper::new(Cond::True), vec![Shift::O, Shift::H], -1.0));
+        assert_eq!(sp.to_constraints(&schedule_config), None);
+    }
 }
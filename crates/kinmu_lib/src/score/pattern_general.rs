@@ -4,8 +4,8 @@
 //! TODO: RollingHash、FSMやTrie木を用いた高速化
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
 };
 
 use kinmu_input_by_file::Check;
@@ -102,6 +102,10 @@ impl ScoreProp<Shift, ShiftState, DayState> for PatternGeneral {
             schedule
         )
     }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for PatternGeneral {
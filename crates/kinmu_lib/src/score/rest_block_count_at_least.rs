@@ -0,0 +1,180 @@
+//! 指定したシフトが指定日数以上連続する休みのまとまりの数が、指定した数に達しない場合に発火するスコア
+//! Condを満たさない日付は飛ばして連続を数える
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp};
+
+macro_rules! eval {
+    ($eval:ident, $can_skip_staff:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        for staff in 0..$staff_config.count {
+            if $self
+                .cond
+                .$can_skip_staff(staff, $staff_config, $day_config)
+            {
+                continue;
+            }
+            let mut is_valid = false;
+            let mut accum = 0;
+            let mut block_count = 0;
+            for day in 0..$day_config.count {
+                if $self.cond.$eval(staff, day, $staff_config, $day_config) {
+                    is_valid = true;
+                    if $self.target_shifts.contains(&$schedule[staff][day]) {
+                        accum += 1;
+                    } else {
+                        if accum >= $self.block_length {
+                            block_count += 1;
+                        }
+                        accum = 0;
+                    }
+                }
+            }
+            if accum >= $self.block_length {
+                block_count += 1;
+            }
+            if is_valid {
+                let d = std::cmp::min(block_count - $self.count, 0) as Score;
+                sum += d * d;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RestBlockCountAtLeast {
+    pub cond: CondWrapper,
+    pub target_shifts: Vec<Shift>,
+    pub block_length: i32,
+    pub count: i32,
+    pub score: Score,
+}
+
+impl RestBlockCountAtLeast {
+    pub fn new(
+        (cond, target_shifts, block_length, count, score): (
+            CondWrapper,
+            Vec<Shift>,
+            i32,
+            i32,
+            Score,
+        ),
+    ) -> Self {
+        Self {
+            cond,
+            target_shifts,
+            block_length,
+            count,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for RestBlockCountAtLeast {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_mut,
+            can_skip_staff_mut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_immut,
+            can_skip_staff_immut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for RestBlockCountAtLeast {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond.check(schedule_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 2連休以上のまとまりが2回あるケース
+    /// 3連休も1回として数える
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![K, Y, N, N, K, K, K, N, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+
+        let mut sp = RestBlockCountAtLeast::new((
+            CondWrapper::new(Cond::True),
+            vec![Shift::K, Shift::Y],
+            2,
+            2,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 2連休以上のまとまりが1回しかないケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![K, N, N, N, K, K, N, N, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+
+        let mut sp = RestBlockCountAtLeast::new((
+            CondWrapper::new(Cond::True),
+            vec![Shift::K, Shift::Y],
+            2,
+            2,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(1.0, score);
+    }
+}
@@ -0,0 +1,213 @@
+//! 指定したシフトが連続する長さが指定した範囲に収まらない場合に、範囲からのずれに比例して発火するスコア
+//! Condを満たさない日付は飛ばして連続を数える
+//! 最初の日付から始まる連続は前の期間から、最後の日付まで続く連続は次の期間に続く可能性があるため、最大値のみ判定する
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp};
+
+macro_rules! eval {
+    ($eval:ident, $can_skip_staff:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0;
+        for staff in 0..$staff_config.count {
+            if $self
+                .cond
+                .$can_skip_staff(staff, $staff_config, $day_config)
+            {
+                continue;
+            }
+            let mut accum = 0;
+            // 最初の日付から続く連続の途中か
+            let mut from_start = true;
+            for day in 0..$day_config.count {
+                if $self.cond.$eval(staff, day, $staff_config, $day_config) {
+                    if $self.target_shifts.contains(&$schedule[staff][day]) {
+                        accum += 1;
+                    } else {
+                        if !from_start && 0 < accum && accum < $self.min_length {
+                            sum += $self.min_length - accum;
+                        }
+                        if $self.max_length < accum {
+                            sum += accum - $self.max_length;
+                        }
+                        accum = 0;
+                        from_start = false;
+                    }
+                }
+            }
+            if $self.max_length < accum {
+                sum += accum - $self.max_length;
+            }
+        }
+        sum as Score * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RunLength {
+    pub cond: CondWrapper,
+    pub target_shifts: Vec<Shift>,
+    pub min_length: i32,
+    pub max_length: i32,
+    pub score: Score,
+}
+
+impl RunLength {
+    pub fn new(
+        (cond, target_shifts, min_length, max_length, score): (
+            CondWrapper,
+            Vec<Shift>,
+            i32,
+            i32,
+            Score,
+        ),
+    ) -> Self {
+        Self {
+            cond,
+            target_shifts,
+            min_length,
+            max_length,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for RunLength {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_mut,
+            can_skip_staff_mut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_immut,
+            can_skip_staff_immut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for RunLength {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.min_length <= self.max_length,
+            "最小の長さ({})が最大の長さ({})より大きいです",
+            self.min_length,
+            self.max_length
+        );
+        self.cond.check(schedule_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 全ての連続が2~3に収まるケース
+    /// 最後の1連続は次の期間に続く可能性があるため無視される
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![I, I, K, I, I, I, K, N, I]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+
+        let mut sp = RunLength::new((CondWrapper::new(Cond::True), vec![Shift::I], 2, 3, 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 1連続と5連続があるケース
+    /// 1連続は最小値から1、5連続は最大値から2ずれている
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![K, I, K, I, I, I, I, I, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+
+        let mut sp = RunLength::new((CondWrapper::new(Cond::True), vec![Shift::I], 2, 3, 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(3.0, score);
+    }
+
+    /// 最初の日付から始まる連続は、最小値に届かなくても無視され、最大値は判定されるケース
+    #[test]
+    fn test_boundary_start() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![I, K, I, I, K], vec![I, I, I, I, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+
+        let mut sp = RunLength::new((CondWrapper::new(Cond::True), vec![Shift::I], 2, 3, 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(1.0, score);
+    }
+
+    /// 最後の日付まで続く連続は、最小値に届かなくても無視され、最大値は判定されるケース
+    #[test]
+    fn test_boundary_end() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![K, I, I, K, I], vec![K, I, I, I, I]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+
+        let mut sp = RunLength::new((CondWrapper::new(Cond::True), vec![Shift::I], 2, 3, 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(1.0, score);
+    }
+}
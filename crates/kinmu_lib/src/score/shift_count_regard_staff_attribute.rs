@@ -1,8 +1,8 @@
 //! 指定したシフトをStaffAttributeで指定した数入らなかった場合に発火するスコア
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState,
-    StaffAttributeNameWrapper, StdScoreProp,
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffAttributeNameWrapper, StdScoreProp,
 };
 
 use kinmu_input_by_file::Check;
@@ -91,6 +91,10 @@ impl ScoreProp<Shift, ShiftState, DayState> for ShiftCountRegardStaffAttribute {
             schedule
         )
     }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for ShiftCountRegardStaffAttribute {
@@ -2,8 +2,8 @@
 //! Scoreのフィールドが正なら前を優先、負なら後ろを優先
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    shift_fixed_or_placeable, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
 };
 
 use kinmu_input_by_file::Check;
@@ -96,6 +96,30 @@ impl ScoreProp<Shift, ShiftState, DayState> for ShiftDirPriority {
             schedule
         )
     }
+
+    /// 職員ごとの評価値はscoreとシフトの数の積になるため、シフトの数が入りうる範囲の端で見積もる
+    fn lower_bound(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Score {
+        let mut sum = 0.0;
+        for staff in 0..staff_config.count {
+            if self
+                .cond
+                .can_skip_staff_immut(staff, staff_config, day_config)
+            {
+                continue;
+            }
+            let mut fixed = 0;
+            let mut placeable = 0;
+            for day in 0..day_config.count {
+                if self.cond.eval_immut(staff, day, staff_config, day_config) {
+                    let (f, p) = shift_fixed_or_placeable(day_config, staff, day, self.shift);
+                    fixed += f as i32;
+                    placeable += p as i32;
+                }
+            }
+            sum += (self.score * fixed as Score).min(self.score * placeable as Score);
+        }
+        sum
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for ShiftDirPriority {
@@ -148,4 +172,39 @@ mod tests {
 
         assert_eq!(-2.0, score);
     }
+
+    /// 固定されたIが1つ、入れられる枠が3つのケース
+    /// 負のスコアでは、入れられる枠全てにIを入れた表で下限に一致する
+    #[test]
+    fn test_lower_bound() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![I, I, I, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.day.requested_schedule = schedule.clone();
+        schedule_config.day.schedule_states = vec![vec![
+            ShiftState::Absolute,
+            ShiftState::Random,
+            ShiftState::Random,
+            ShiftState::Absolute,
+        ]];
+
+        let sp = ShiftDirPriority::new((CondWrapper::new(Cond::True), Shift::I, 1.0));
+        assert_eq!(
+            1.0,
+            sp.lower_bound(&schedule_config.staff, &schedule_config.day)
+        );
+
+        let sp = ShiftDirPriority::new((CondWrapper::new(Cond::True), Shift::I, -1.0));
+        let lower_bound = sp.lower_bound(&schedule_config.staff, &schedule_config.day);
+        assert_eq!(-3.0, lower_bound);
+        assert_eq!(
+            lower_bound,
+            sp.eval_immut(&schedule_config.staff, &schedule_config.day, &schedule)
+        );
+    }
 }
@@ -1,7 +1,8 @@
 //! 指定したシフトどうしの距離が遠い場合に高いペナルティを与えるスコア
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StdScoreProp,
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StdScoreProp,
 };
 
 use kinmu_input_by_file::Check;
@@ -85,6 +86,10 @@ impl ScoreProp<Shift, ShiftState, DayState> for ShiftDistance {
             schedule
         )
     }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for ShiftDistance {
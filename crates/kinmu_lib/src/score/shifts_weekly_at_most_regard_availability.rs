@@ -0,0 +1,195 @@
+//! 週ごとの指定したシフトの数がstaff.availabilityで指定した上限を超えた場合に発火するスコア
+//! 週はDayAttributeで指定した値が同じ日付の集まりとして扱う
+//! 上限の指定がない職員は対象外
+
+use crate::DayAttributeNameWrapper;
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{DayAttributeName, Score, ScoreProp};
+
+use std::collections::HashMap;
+
+macro_rules! eval {
+    ($eval:ident, $can_skip_staff:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        let weeks = $day_config.attributes.get(&$self.week_attribute).unwrap();
+        for staff in 0..$staff_config.count {
+            let max_count = match $staff_config.get_max_shifts_per_week(staff) {
+                Some(m) => m,
+                None => continue,
+            };
+            if $self
+                .cond
+                .$can_skip_staff(staff, $staff_config, $day_config)
+            {
+                continue;
+            }
+            let mut count_map: HashMap<i32, i32> = HashMap::new();
+            for day in 0..$day_config.count {
+                if $self.cond.$eval(staff, day, $staff_config, $day_config)
+                    && $self.target_shifts.contains(&$schedule[staff][day])
+                {
+                    *count_map.entry(weeks[day]).or_insert(0) += 1;
+                }
+            }
+            for count in count_map.values() {
+                let d = std::cmp::max(*count - max_count, 0) as Score;
+                sum += d * d;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShiftsWeeklyAtMostRegardAvailability {
+    pub cond: CondWrapper,
+    pub target_shifts: Vec<Shift>,
+    pub week_attribute: DayAttributeName,
+    pub score: Score,
+}
+
+impl ShiftsWeeklyAtMostRegardAvailability {
+    pub fn new(
+        (cond, target_shifts, week_attribute, score): (
+            CondWrapper,
+            Vec<Shift>,
+            DayAttributeName,
+            Score,
+        ),
+    ) -> Self {
+        Self {
+            cond,
+            target_shifts,
+            week_attribute,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for ShiftsWeeklyAtMostRegardAvailability {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_mut,
+            can_skip_staff_mut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_immut,
+            can_skip_staff_immut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for ShiftsWeeklyAtMostRegardAvailability {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond
+            .check(schedule_config)
+            .and(DayAttributeNameWrapper(&self.week_attribute).check(schedule_config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use kinmu_model::Availability;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 上限の指定がある職員が週2回以内、指定がない職員は制限なしのケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K, N, I, A, K], vec![N, N, N, N, N, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config
+            .day
+            .attributes
+            .insert(String::from("week"), vec![0, 0, 0, 1, 1, 1]);
+        schedule_config.staff.availabilities.push(Availability {
+            staff: 0,
+            unavailable_ranges: vec![],
+            max_shifts_per_week: Some(2),
+        });
+
+        let mut sp = ShiftsWeeklyAtMostRegardAvailability::new((
+            CondWrapper::new(Cond::True),
+            vec![Shift::N, Shift::I, Shift::A],
+            String::from("week"),
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 1週目が上限を1回超えるケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, N, K, K, N], vec![N, N, N, N, N, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config
+            .day
+            .attributes
+            .insert(String::from("week"), vec![0, 0, 0, 1, 1, 1]);
+        schedule_config.staff.availabilities.push(Availability {
+            staff: 0,
+            unavailable_ranges: vec![],
+            max_shifts_per_week: Some(2),
+        });
+
+        let mut sp = ShiftsWeeklyAtMostRegardAvailability::new((
+            CondWrapper::new(Cond::True),
+            vec![Shift::N, Shift::I, Shift::A],
+            String::from("week"),
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(1.0, score);
+    }
+}
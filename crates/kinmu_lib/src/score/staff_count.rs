@@ -1,10 +1,11 @@
 //! 指定したシフトが指定した数いない場合に発火するスコア
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    diagnose_staff_count, staff_count_lower_bound, CondWrapper, DayConfig, DayState, Schedule,
+    ScheduleConfig, Shift, ShiftState, StaffConfig, StdScoreProp,
 };
 
+use kinmu_generator_with_solver::{Constraint, ToConstraints};
 use kinmu_input_by_file::Check;
 use kinmu_model::{Score, ScoreProp};
 
@@ -85,6 +86,28 @@ impl ScoreProp<Shift, ShiftState, DayState> for StaffCount {
             schedule
         )
     }
+
+    fn lower_bound(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Score {
+        staff_count_lower_bound(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |_| (self.count, Some(self.count)),
+        )
+    }
+
+    fn diagnose(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Vec<String> {
+        diagnose_staff_count(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |_| (self.count, Some(self.count)),
+        )
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for StaffCount {
@@ -93,6 +116,36 @@ impl Check<StdScoreProp, Shift, ShiftState, DayState> for StaffCount {
     }
 }
 
+impl ToConstraints<StdScoreProp, Shift, ShiftState, DayState> for StaffCount {
+    /// 条件に合う日ごとに、シフトの人数の制約に変換する
+    fn to_constraints(&self, schedule_config: &ScheduleConfig) -> Option<Vec<Constraint<Shift>>> {
+        if self.score <= 0.0 || self.count < 0 {
+            return None;
+        }
+        let staff_config = &schedule_config.staff;
+        let day_config = &schedule_config.day;
+        let mut constraints = Vec::new();
+        for day in 0..day_config.count {
+            if self.cond.can_skip_day_immut(day, staff_config, day_config) {
+                continue;
+            }
+            let cells: Vec<_> = (0..staff_config.count)
+                .filter(|&staff| self.cond.eval_immut(staff, day, staff_config, day_config))
+                .map(|staff| (staff, day))
+                .collect();
+            if !cells.is_empty() {
+                constraints.push(Constraint::Count {
+                    max: self.count as usize,
+                    cells,
+                    shift: self.shift,
+                    min: self.count as usize,
+                });
+            }
+        }
+        Some(constraints)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Cond;
@@ -156,4 +209,112 @@ mod tests {
 
         assert_eq!(1.0, score);
     }
+
+    /// Yが多く、Nを2人入れられない日があるケース
+    /// 4日目はすべての枠がAbsoluteだが、同様に報告される
+    /// 読み込み時のチェックではエラーにならない
+    #[test]
+    fn test_diagnose() {
+        let requested_schedule = {
+            use Shift::*;
+            vec![vec![Y, U, U, N], vec![Y, U, Y, Y], vec![U, U, Y, Y]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = requested_schedule[0].len();
+        schedule_config.staff.count = requested_schedule.len();
+        schedule_config.day.schedule_states = requested_schedule
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|s| match s {
+                        Shift::U => ShiftState::Random,
+                        _ => ShiftState::Absolute,
+                    })
+                    .collect()
+            })
+            .collect();
+        schedule_config.day.requested_schedule = requested_schedule;
+
+        let sp = StaffCount::new((CondWrapper::new(Cond::True), Shift::N, 2, 1.0));
+        assert!(sp.check(&schedule_config).is_ok());
+        let messages = sp.diagnose(&schedule_config.staff, &schedule_config.day);
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].starts_with("1日目"));
+        assert!(messages[1].starts_with("3日目"));
+        assert!(messages[2].starts_with("4日目"));
+
+        let sp = StaffCount::new((CondWrapper::new(Cond::True), Shift::N, 1, 1.0));
+        assert!(sp
+            .diagnose(&schedule_config.staff, &schedule_config.day)
+            .is_empty());
+
+        let sp = StaffCount::new((CondWrapper::new(Cond::True), Shift::N, 2, -1.0));
+        assert!(sp
+            .diagnose(&schedule_config.staff, &schedule_config.day)
+            .is_empty());
+    }
+
+    /// 1日目は固定でNが0人、2日目はNを2人まで入れられるケース
+    #[test]
+    fn test_lower_bound() {
+        let requested_schedule = {
+            use Shift::*;
+            vec![vec![K, U], vec![Y, U], vec![K, Y]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = requested_schedule[0].len();
+        schedule_config.staff.count = requested_schedule.len();
+        schedule_config.day.schedule_states = requested_schedule
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|s| match s {
+                        Shift::U => ShiftState::Random,
+                        _ => ShiftState::Absolute,
+                    })
+                    .collect()
+            })
+            .collect();
+        schedule_config.day.requested_schedule = requested_schedule;
+
+        let sp = StaffCount::new((CondWrapper::new(Cond::True), Shift::N, 1, 10.0));
+        let bound = sp.lower_bound(&schedule_config.staff, &schedule_config.day);
+        assert_eq!(10.0, bound);
+
+        let sp = StaffCount::new((CondWrapper::new(Cond::True), Shift::N, 3, 10.0));
+        let bound = sp.lower_bound(&schedule_config.staff, &schedule_config.day);
+        assert_eq!(100.0, bound);
+    }
+
+    /// 日ごとに、ちょうどcount人の制約になるケース
+    #[test]
+    fn test_to_constraints() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = 2;
+        schedule_config.staff.count = 2;
+
+        let sp = StaffCount::new((CondWrapper::new(Cond::True), Shift::N, 1, 1.0));
+        assert_eq!(
+            sp.to_constraints(&schedule_config),
+            Some(vec![
+                Constraint::Count {
+                    cells: vec![(0, 0), (1, 0)],
+                    shift: Shift::N,
+                    min: 1,
+                    max: 1,
+                },
+                Constraint::Count {
+                    cells: vec![(0, 1), (1, 1)],
+                    shift: Shift::N,
+                    min: 1,
+                    max: 1,
+                },
+            ])
+        );
+
+        let sp = StaffCount::new((CondWrapper::new(Cond::True), Shift::N, 1, 0.0));
+        assert_eq!(sp.to_constraints(&schedule_config), None);
+    }
 }
@@ -1,10 +1,11 @@
 //! 指定したシフトが指定した数より少ない場合に発火するスコア
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    diagnose_staff_count, staff_count_lower_bound, CondWrapper, DayConfig, DayState, Schedule,
+    ScheduleConfig, Shift, ShiftState, StaffConfig, StdScoreProp,
 };
 
+use kinmu_generator_with_solver::{Constraint, ToConstraints};
 use kinmu_input_by_file::Check;
 use kinmu_model::{Score, ScoreProp};
 
@@ -85,6 +86,28 @@ impl ScoreProp<Shift, ShiftState, DayState> for StaffCountAtLeast {
             schedule
         )
     }
+
+    fn lower_bound(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Score {
+        staff_count_lower_bound(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |_| (self.count, None),
+        )
+    }
+
+    fn diagnose(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Vec<String> {
+        diagnose_staff_count(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |_| (self.count, None),
+        )
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for StaffCountAtLeast {
@@ -93,6 +116,36 @@ impl Check<StdScoreProp, Shift, ShiftState, DayState> for StaffCountAtLeast {
     }
 }
 
+impl ToConstraints<StdScoreProp, Shift, ShiftState, DayState> for StaffCountAtLeast {
+    /// 条件に合う日ごとに、シフトの人数の制約に変換する
+    fn to_constraints(&self, schedule_config: &ScheduleConfig) -> Option<Vec<Constraint<Shift>>> {
+        if self.score <= 0.0 || self.count < 0 {
+            return None;
+        }
+        let staff_config = &schedule_config.staff;
+        let day_config = &schedule_config.day;
+        let mut constraints = Vec::new();
+        for day in 0..day_config.count {
+            if self.cond.can_skip_day_immut(day, staff_config, day_config) {
+                continue;
+            }
+            let cells: Vec<_> = (0..staff_config.count)
+                .filter(|&staff| self.cond.eval_immut(staff, day, staff_config, day_config))
+                .map(|staff| (staff, day))
+                .collect();
+            if !cells.is_empty() {
+                constraints.push(Constraint::Count {
+                    max: cells.len(),
+                    cells,
+                    shift: self.shift,
+                    min: self.count as usize,
+                });
+            }
+        }
+        Some(constraints)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Cond;
@@ -137,4 +190,26 @@ mod tests {
 
         assert_eq!(1.0, score);
     }
+
+    /// 日ごとに、count人以上の制約になるケース
+    #[test]
+    fn test_to_constraints() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = 1;
+        schedule_config.staff.count = 3;
+
+        let sp = StaffCountAtLeast::new((CondWrapper::new(Cond::True), Shift::N, 2, 1.0));
+        assert_eq!(
+            sp.to_constraints(&schedule_config),
+            Some(vec![Constraint::Count {
+                cells: vec![(0, 0), (1, 0), (2, 0)],
+                shift: Shift::N,
+                min: 2,
+                max: 3,
+            }])
+        );
+
+        let sp = StaffCountAtLeast::new((CondWrapper::new(Cond::True), Shift::N, -1, 1.0));
+        assert_eq!(sp.to_constraints(&schedule_config), None);
+    }
 }
@@ -0,0 +1,222 @@
+//! 指定したシフトの人数が指定した範囲に収まらない場合に発火するスコア
+//! CondにStaffWithAttributeを指定すると、特定の属性を持つ職員のみを数える
+//! 例えば夜勤に必ずリーダーを1人以上入れる、夜勤の新人を1人までにするなどの指定ができる
+
+use super::{
+    diagnose_staff_count, staff_count_lower_bound, CondWrapper, DayConfig, DayState, Schedule,
+    ScheduleConfig, Shift, ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp};
+
+macro_rules! eval {
+    ($eval:ident, $can_skip_day:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        for day in 0..$day_config.count {
+            if $self.cond.$can_skip_day(day, $staff_config, $day_config) {
+                continue;
+            }
+            let mut is_valid = false;
+            let mut staff_count = 0;
+            for staff in 0..$staff_config.count {
+                if $self.cond.$eval(staff, day, $staff_config, $day_config) {
+                    is_valid = true;
+                    if $schedule[staff][day] == $self.shift {
+                        staff_count += 1;
+                    }
+                }
+            }
+            if is_valid {
+                let d = (std::cmp::max($self.min_count - staff_count, 0)
+                    + std::cmp::max(staff_count - $self.max_count, 0))
+                    as Score;
+                sum += d * d;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct StaffCountInRange {
+    pub cond: CondWrapper,
+    pub shift: Shift,
+    pub min_count: i32,
+    pub max_count: i32,
+    pub score: Score,
+}
+
+impl StaffCountInRange {
+    pub fn new(
+        (cond, shift, min_count, max_count, score): (CondWrapper, Shift, i32, i32, Score),
+    ) -> Self {
+        Self {
+            cond,
+            shift,
+            min_count,
+            max_count,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for StaffCountInRange {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_mut,
+            can_skip_day_mut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_immut,
+            can_skip_day_immut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn lower_bound(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Score {
+        staff_count_lower_bound(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |_| (self.min_count, Some(self.max_count)),
+        )
+    }
+
+    fn diagnose(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Vec<String> {
+        diagnose_staff_count(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |_| (self.min_count, Some(self.max_count)),
+        )
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for StaffCountInRange {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.min_count <= self.max_count,
+            "最小の人数({})が最大の人数({})より大きいです",
+            self.min_count,
+            self.max_count
+        );
+        self.cond.check(schedule_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use kinmu_model::Staff;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 毎日Iにリーダーが1人いるケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![I, A, I], vec![N, I, A], vec![I, I, I]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        for leader in [1, 1, 0] {
+            schedule_config.staff.list.push(Staff {
+                name: String::from(""),
+                attributes: vec![leader],
+            });
+        }
+        schedule_config
+            .staff
+            .attribute_map
+            .names
+            .push(String::from("leader"));
+        schedule_config
+            .staff
+            .attribute_map
+            .name_to_index
+            .insert(String::from("leader"), 0);
+
+        let mut sp = StaffCountInRange::new((
+            CondWrapper::new(Cond::StaffWithAttribute((String::from("leader"), 1))),
+            Shift::I,
+            1,
+            1,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// Iのリーダーが0人の日と2人の日があるケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![I, A, N], vec![I, K, N], vec![N, I, I]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        for leader in [1, 1, 0] {
+            schedule_config.staff.list.push(Staff {
+                name: String::from(""),
+                attributes: vec![leader],
+            });
+        }
+        schedule_config
+            .staff
+            .attribute_map
+            .names
+            .push(String::from("leader"));
+        schedule_config
+            .staff
+            .attribute_map
+            .name_to_index
+            .insert(String::from("leader"), 0);
+
+        let mut sp = StaffCountInRange::new((
+            CondWrapper::new(Cond::StaffWithAttribute((String::from("leader"), 1))),
+            Shift::I,
+            1,
+            1,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(3.0, score);
+    }
+}
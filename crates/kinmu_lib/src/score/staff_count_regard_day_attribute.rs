@@ -3,8 +3,8 @@
 use crate::DayAttributeNameWrapper;
 
 use super::{
-    CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift, ShiftState, StaffConfig,
-    StdScoreProp,
+    diagnose_staff_count, staff_count_lower_bound, CondWrapper, DayConfig, DayState, Schedule,
+    ScheduleConfig, Shift, ShiftState, StaffConfig, StdScoreProp,
 };
 
 use kinmu_input_by_file::Check;
@@ -90,6 +90,36 @@ impl ScoreProp<Shift, ShiftState, DayState> for StaffCountRegardDayAttribute {
             schedule
         )
     }
+
+    fn lower_bound(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Score {
+        let counts = &day_config.attributes[&self.attribute];
+        staff_count_lower_bound(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |day| {
+                let count = counts[day];
+                (count, Some(count))
+            },
+        )
+    }
+
+    fn diagnose(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Vec<String> {
+        let counts = &day_config.attributes[&self.attribute];
+        diagnose_staff_count(
+            staff_config,
+            day_config,
+            &self.cond,
+            self.shift,
+            self.score,
+            |day| {
+                let count = counts[day];
+                (count, Some(count))
+            },
+        )
+    }
 }
 
 impl Check<StdScoreProp, Shift, ShiftState, DayState> for StaffCountRegardDayAttribute {
@@ -0,0 +1,207 @@
+//! day.wishesで指定した希望が叶わなかった場合に、その優先度に比例して発火するスコア
+
+use super::{
+    shift_fixed_or_placeable, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp};
+
+macro_rules! eval {
+    ($eval:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        for wish in &$day_config.wishes {
+            if $self
+                .cond
+                .$eval(wish.staff, wish.day, $staff_config, $day_config)
+                && $schedule[wish.staff][wish.day] != wish.shift
+            {
+                sum += wish.priority;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnmetWish {
+    pub cond: CondWrapper,
+    pub score: Score,
+}
+
+impl UnmetWish {
+    pub fn new((cond, score): (CondWrapper, Score)) -> Self {
+        Self { cond, score }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for UnmetWish {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(eval_mut, self, staff_config, day_config, schedule)
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(eval_immut, self, staff_config, day_config, schedule)
+    }
+
+    /// 希望のシフトを入れられない枠の希望は必ず叶わないものとして見積もる
+    /// scoreが負の場合は、必ず叶う希望以外が叶わないものとして見積もる
+    fn lower_bound(&self, staff_config: &StaffConfig, day_config: &DayConfig) -> Score {
+        let mut must_unmet = 0.0;
+        let mut may_unmet = 0.0;
+        for wish in &day_config.wishes {
+            if !self
+                .cond
+                .eval_immut(wish.staff, wish.day, staff_config, day_config)
+            {
+                continue;
+            }
+            let (fixed, placeable) =
+                shift_fixed_or_placeable(day_config, wish.staff, wish.day, wish.shift);
+            if !placeable {
+                must_unmet += wish.priority;
+            }
+            if !fixed {
+                may_unmet += wish.priority;
+            }
+        }
+        (must_unmet * self.score).min(may_unmet * self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for UnmetWish {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond.check(schedule_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use kinmu_model::Wish;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 全ての希望が叶っているケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K, N], vec![K, N, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.day.wishes = vec![
+            Wish {
+                staff: 0,
+                day: 1,
+                shift: Shift::K,
+                priority: 2.0,
+            },
+            Wish {
+                staff: 1,
+                day: 2,
+                shift: Shift::N,
+                priority: 1.0,
+            },
+        ];
+
+        let mut sp = UnmetWish::new((CondWrapper::new(Cond::True), 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 優先度2の希望が叶っていないケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, K], vec![K, N, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.day.wishes = vec![
+            Wish {
+                staff: 0,
+                day: 1,
+                shift: Shift::K,
+                priority: 2.0,
+            },
+            Wish {
+                staff: 1,
+                day: 2,
+                shift: Shift::N,
+                priority: 1.0,
+            },
+        ];
+
+        let mut sp = UnmetWish::new((CondWrapper::new(Cond::True), 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(2.0, score);
+    }
+
+    /// 入れられない枠の希望は必ず叶わず、負のスコアでは叶いうる希望が全て叶わないものとするケース
+    #[test]
+    fn test_lower_bound() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, N], vec![N, N, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.day.wishes = vec![
+            Wish {
+                staff: 0,
+                day: 1,
+                shift: Shift::K,
+                priority: 2.0,
+            },
+            Wish {
+                staff: 1,
+                day: 2,
+                shift: Shift::N,
+                priority: 1.0,
+            },
+        ];
+        schedule_config.day.requested_schedule = schedule;
+        schedule_config.day.schedule_states = vec![
+            vec![ShiftState::Random, ShiftState::Absolute, ShiftState::Random],
+            vec![ShiftState::Random; 3],
+        ];
+
+        let sp = UnmetWish::new((CondWrapper::new(Cond::True), 1.0));
+        assert_eq!(
+            2.0,
+            sp.lower_bound(&schedule_config.staff, &schedule_config.day)
+        );
+
+        let sp = UnmetWish::new((CondWrapper::new(Cond::True), -1.0));
+        assert_eq!(
+            -3.0,
+            sp.lower_bound(&schedule_config.staff, &schedule_config.day)
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! 週末を丸ごと休めた回数が指定した数に達しない場合に発火するスコア
+//! Condを満たす日付が連続する範囲を1つの週末として扱う
+//! 例えばCondにDayState Hを指定すると、連続する休日を1つの週末とみなす
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp};
+
+macro_rules! eval {
+    ($eval:ident, $can_skip_staff:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        for staff in 0..$staff_config.count {
+            if $self
+                .cond
+                .$can_skip_staff(staff, $staff_config, $day_config)
+            {
+                continue;
+            }
+            let mut is_valid = false;
+            let mut in_weekend = false;
+            let mut all_off = true;
+            let mut off_count = 0;
+            for day in 0..$day_config.count {
+                if $self.cond.$eval(staff, day, $staff_config, $day_config) {
+                    is_valid = true;
+                    in_weekend = true;
+                    all_off &= $self.target_shifts.contains(&$schedule[staff][day]);
+                } else {
+                    if in_weekend && all_off {
+                        off_count += 1;
+                    }
+                    in_weekend = false;
+                    all_off = true;
+                }
+            }
+            if in_weekend && all_off {
+                off_count += 1;
+            }
+            if is_valid {
+                let d = std::cmp::min(off_count - $self.count, 0) as Score;
+                sum += d * d;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WeekendOffCountAtLeast {
+    pub cond: CondWrapper,
+    pub target_shifts: Vec<Shift>,
+    pub count: i32,
+    pub score: Score,
+}
+
+impl WeekendOffCountAtLeast {
+    pub fn new((cond, target_shifts, count, score): (CondWrapper, Vec<Shift>, i32, Score)) -> Self {
+        Self {
+            cond,
+            target_shifts,
+            count,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for WeekendOffCountAtLeast {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_mut,
+            can_skip_staff_mut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_immut,
+            can_skip_staff_immut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for WeekendOffCountAtLeast {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond.check(schedule_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 2回の週末をどちらも丸ごと休んでいるケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K, Y, N, N, K, K, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.day.days = {
+            use DayState::*;
+            vec![
+                Weekday, Holiday, Holiday, Weekday, Weekday, Holiday, Holiday, Weekday,
+            ]
+        };
+
+        let mut sp = WeekendOffCountAtLeast::new((
+            CondWrapper::new(Cond::DayState(DayState::Holiday)),
+            vec![Shift::K, Shift::Y],
+            2,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 片方の週末で1日しか休めていないケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K, K, N, N, K, N, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.day.days = {
+            use DayState::*;
+            vec![
+                Weekday, Holiday, Holiday, Weekday, Weekday, Holiday, Holiday, Weekday,
+            ]
+        };
+
+        let mut sp = WeekendOffCountAtLeast::new((
+            CondWrapper::new(Cond::DayState(DayState::Holiday)),
+            vec![Shift::K, Shift::Y],
+            2,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(1.0, score);
+    }
+}
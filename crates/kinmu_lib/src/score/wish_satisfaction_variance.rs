@@ -0,0 +1,164 @@
+//! 職員ごとのday.wishesで指定した希望が叶った割合の分散に定数を掛けた値を返す
+//! 割合は希望の優先度で重みづけして計算し、希望のない職員は除外する
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftState, StaffConfig, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp};
+
+macro_rules! eval {
+    ($eval:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        // 職員ごとの(叶った希望の優先度の和, 全ての希望の優先度の和)
+        let mut sums = vec![(0.0, 0.0); $staff_config.count];
+        for wish in &$day_config.wishes {
+            if $self
+                .cond
+                .$eval(wish.staff, wish.day, $staff_config, $day_config)
+            {
+                if $schedule[wish.staff][wish.day] == wish.shift {
+                    sums[wish.staff].0 += wish.priority;
+                }
+                sums[wish.staff].1 += wish.priority;
+            }
+        }
+        let rs: Vec<Score> = sums
+            .iter()
+            .filter(|(_, total)| 0.0 < *total)
+            .map(|(granted, total)| granted / total)
+            .collect();
+
+        if rs.is_empty() {
+            0.0
+        } else {
+            // 平均をとる
+            let mut ave = 0.0;
+            for r in &rs {
+                ave += *r;
+            }
+            ave /= rs.len() as f32;
+
+            // 分散をとる
+            let mut var = 0.0;
+            for r in &rs {
+                let d = *r - ave;
+                var += d * d;
+            }
+            var /= rs.len() as f32;
+
+            var * $self.score
+        }
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WishSatisfactionVariance {
+    pub cond: CondWrapper,
+    pub score: Score,
+}
+
+impl WishSatisfactionVariance {
+    pub fn new((cond, score): (CondWrapper, Score)) -> Self {
+        Self { cond, score }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for WishSatisfactionVariance {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(eval_mut, self, staff_config, day_config, schedule)
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(eval_immut, self, staff_config, day_config, schedule)
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for WishSatisfactionVariance {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond.check(schedule_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use kinmu_model::Wish;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 全員の希望が半分ずつ叶っているケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![K, N, N], vec![N, N, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        for staff in 0..schedule.len() {
+            for day in [0, 2] {
+                schedule_config.day.wishes.push(Wish {
+                    staff,
+                    day,
+                    shift: Shift::K,
+                    priority: 1.0,
+                });
+            }
+        }
+
+        let mut sp = WishSatisfactionVariance::new((CondWrapper::new(Cond::True), 1.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 片方の希望だけが全て叶っているケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![K, N, K], vec![N, K, N]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        for staff in 0..schedule.len() {
+            for day in [0, 2] {
+                schedule_config.day.wishes.push(Wish {
+                    staff,
+                    day,
+                    shift: Shift::K,
+                    priority: 1.0,
+                });
+            }
+        }
+
+        let mut sp = WishSatisfactionVariance::new((CondWrapper::new(Cond::True), 4.0));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(1.0, score);
+    }
+}
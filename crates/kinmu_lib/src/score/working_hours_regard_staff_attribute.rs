@@ -0,0 +1,193 @@
+//! 期間全体の勤務時間とStaffAttributeで指定した時間の差が許容幅を超えた場合に発火するスコア
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftHoursWrapper, ShiftState, StaffAttributeNameWrapper, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{Score, ScoreProp, StaffAttributeName, StaffConfig};
+
+macro_rules! eval {
+    ($eval:ident, $can_skip_staff:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        for staff in 0..$staff_config.count {
+            if $self
+                .cond
+                .$can_skip_staff(staff, $staff_config, $day_config)
+            {
+                continue;
+            }
+            let mut is_valid = false;
+            let mut hours = 0.0;
+            for day in 0..$day_config.count {
+                if $self.cond.$eval(staff, day, $staff_config, $day_config) {
+                    is_valid = true;
+                    hours += $day_config.get_shift_hours(&$schedule[staff][day]);
+                }
+            }
+            if is_valid {
+                let hours_needed = $staff_config.get_attribute(staff, &$self.attribute) as f32;
+                let d = ((hours - hours_needed).abs() - $self.tolerance).max(0.0);
+                sum += d * d;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WorkingHoursRegardStaffAttribute {
+    pub cond: CondWrapper,
+    pub attribute: StaffAttributeName,
+    pub tolerance: f32,
+    pub score: Score,
+}
+
+impl WorkingHoursRegardStaffAttribute {
+    pub fn new(
+        (cond, attribute, tolerance, score): (CondWrapper, StaffAttributeName, f32, Score),
+    ) -> Self {
+        Self {
+            cond,
+            attribute,
+            tolerance,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for WorkingHoursRegardStaffAttribute {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_mut,
+            can_skip_staff_mut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_immut,
+            can_skip_staff_immut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for WorkingHoursRegardStaffAttribute {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond
+            .check(schedule_config)
+            .and(ShiftHoursWrapper.check(schedule_config))
+            .and(StaffAttributeNameWrapper(&self.attribute).check(schedule_config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use kinmu_model::Staff;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 勤務時間の差が許容幅に収まるケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, K, N, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.staff.list.push(Staff {
+            name: String::from(""),
+            attributes: vec![24],
+        });
+        schedule_config
+            .staff
+            .attribute_map
+            .names
+            .push(String::from("contract_hours"));
+        schedule_config
+            .staff
+            .attribute_map
+            .name_to_index
+            .insert(String::from("contract_hours"), 0);
+        schedule_config.day.shift_hours = vec![(Shift::N, 7.5)];
+
+        let mut sp = WorkingHoursRegardStaffAttribute::new((
+            CondWrapper::new(Cond::True),
+            String::from("contract_hours"),
+            2.0,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 勤務時間の不足が許容幅を3時間超えるケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K, K, N, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.staff.list.push(Staff {
+            name: String::from(""),
+            attributes: vec![24],
+        });
+        schedule_config
+            .staff
+            .attribute_map
+            .names
+            .push(String::from("contract_hours"));
+        schedule_config
+            .staff
+            .attribute_map
+            .name_to_index
+            .insert(String::from("contract_hours"), 0);
+        schedule_config.day.shift_hours = vec![(Shift::N, 8.0)];
+
+        let mut sp = WorkingHoursRegardStaffAttribute::new((
+            CondWrapper::new(Cond::True),
+            String::from("contract_hours"),
+            5.0,
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(9.0, score);
+    }
+}
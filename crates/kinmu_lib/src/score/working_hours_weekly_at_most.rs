@@ -0,0 +1,212 @@
+//! 週ごとの勤務時間がStaffAttributeで指定した上限を超えた場合に発火するスコア
+//! 週はDayAttributeで指定した値が同じ日付の集まりとして扱う
+
+use crate::DayAttributeNameWrapper;
+
+use super::{
+    penalty_lower_bound, CondWrapper, DayConfig, DayState, Schedule, ScheduleConfig, Shift,
+    ShiftHoursWrapper, ShiftState, StaffAttributeNameWrapper, StdScoreProp,
+};
+
+use kinmu_input_by_file::Check;
+use kinmu_model::{DayAttributeName, Score, ScoreProp, StaffAttributeName, StaffConfig};
+
+use std::collections::HashMap;
+
+macro_rules! eval {
+    ($eval:ident, $can_skip_staff:ident, $self:expr, $staff_config:expr, $day_config:expr, $schedule:expr) => {{
+        let mut sum = 0.0;
+        let weeks = $day_config.attributes.get(&$self.week_attribute).unwrap();
+        for staff in 0..$staff_config.count {
+            if $self
+                .cond
+                .$can_skip_staff(staff, $staff_config, $day_config)
+            {
+                continue;
+            }
+            let mut hours_map: HashMap<i32, f32> = HashMap::new();
+            for day in 0..$day_config.count {
+                if $self.cond.$eval(staff, day, $staff_config, $day_config) {
+                    *hours_map.entry(weeks[day]).or_insert(0.0) +=
+                        $day_config.get_shift_hours(&$schedule[staff][day]);
+                }
+            }
+            let hours_limit = $staff_config.get_attribute(staff, &$self.limit_attribute) as f32;
+            for hours in hours_map.values() {
+                let d = (*hours - hours_limit).max(0.0);
+                sum += d * d;
+            }
+        }
+        sum * $self.score
+    }};
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WorkingHoursWeeklyAtMost {
+    pub cond: CondWrapper,
+    pub week_attribute: DayAttributeName,
+    pub limit_attribute: StaffAttributeName,
+    pub score: Score,
+}
+
+impl WorkingHoursWeeklyAtMost {
+    pub fn new(
+        (cond, week_attribute, limit_attribute, score): (
+            CondWrapper,
+            DayAttributeName,
+            StaffAttributeName,
+            Score,
+        ),
+    ) -> Self {
+        Self {
+            cond,
+            week_attribute,
+            limit_attribute,
+            score,
+        }
+    }
+}
+
+impl ScoreProp<Shift, ShiftState, DayState> for WorkingHoursWeeklyAtMost {
+    fn eval_mut(
+        &mut self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_mut,
+            can_skip_staff_mut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn eval_immut(
+        &self,
+        staff_config: &StaffConfig,
+        day_config: &DayConfig,
+        schedule: &Schedule,
+    ) -> Score {
+        eval!(
+            eval_immut,
+            can_skip_staff_immut,
+            self,
+            staff_config,
+            day_config,
+            schedule
+        )
+    }
+
+    fn lower_bound(&self, _staff_config: &StaffConfig, _day_config: &DayConfig) -> Score {
+        penalty_lower_bound(self.score)
+    }
+}
+
+impl Check<StdScoreProp, Shift, ShiftState, DayState> for WorkingHoursWeeklyAtMost {
+    fn check(&self, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.cond
+            .check(schedule_config)
+            .and(ShiftHoursWrapper.check(schedule_config))
+            .and(DayAttributeNameWrapper(&self.week_attribute).check(schedule_config))
+            .and(StaffAttributeNameWrapper(&self.limit_attribute).check(schedule_config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cond;
+
+    use kinmu_model::Staff;
+
+    use super::super::super::ScheduleConfig;
+    use super::*;
+
+    /// 週ごとの勤務時間が上限以内のケース
+    #[test]
+    fn test_pass() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, K, I, A, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config
+            .day
+            .attributes
+            .insert(String::from("week"), vec![0, 0, 0, 1, 1, 1]);
+        schedule_config.staff.list.push(Staff {
+            name: String::from(""),
+            attributes: vec![16],
+        });
+        schedule_config
+            .staff
+            .attribute_map
+            .names
+            .push(String::from("max_hours"));
+        schedule_config
+            .staff
+            .attribute_map
+            .name_to_index
+            .insert(String::from("max_hours"), 0);
+        schedule_config.day.shift_hours = vec![(Shift::N, 8.0), (Shift::I, 16.0)];
+
+        let mut sp = WorkingHoursWeeklyAtMost::new((
+            CondWrapper::new(Cond::True),
+            String::from("week"),
+            String::from("max_hours"),
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(0.0, score);
+    }
+
+    /// 1週目の勤務時間が上限を2時間超えるケース
+    #[test]
+    fn test_hit() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, N, O, K, K, K]]
+        };
+
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.staff.count = schedule.len();
+        schedule_config
+            .day
+            .attributes
+            .insert(String::from("week"), vec![0, 0, 0, 1, 1, 1]);
+        schedule_config.staff.list.push(Staff {
+            name: String::from(""),
+            attributes: vec![16],
+        });
+        schedule_config
+            .staff
+            .attribute_map
+            .names
+            .push(String::from("max_hours"));
+        schedule_config
+            .staff
+            .attribute_map
+            .name_to_index
+            .insert(String::from("max_hours"), 0);
+        schedule_config.day.shift_hours = vec![(Shift::N, 8.0), (Shift::O, 2.0)];
+
+        let mut sp = WorkingHoursWeeklyAtMost::new((
+            CondWrapper::new(Cond::True),
+            String::from("week"),
+            String::from("max_hours"),
+            1.0,
+        ));
+
+        let score = sp.eval_mut(&schedule_config.staff, &schedule_config.day, &schedule);
+
+        assert_eq!(4.0, score);
+    }
+}
@@ -1,4 +1,4 @@
-use super::super::{Schedule, ScheduleConfig, ScheduleState, Shift, ShiftState};
+use super::super::{Schedule, ScheduleConfig, ScheduleState, Shift};
 
 use kinmu_model::Score;
 
@@ -14,7 +14,7 @@ use rand::Rng;
 // 7.  ランダムなNをKで置き換える
 // 8.  K,Iの数が変わっていないことを確かめる
 // 9.  Iの後にAが来ているか調べる
-// 10. Absoluteが動いていないか調べる
+// 10. 変化した枠に入れられないシフトが入っていないか調べる
 
 /// 指定したシフトが指定した行にいくつ含まれるか
 macro_rules! count_waku_row {
@@ -30,6 +30,7 @@ macro_rules! count_waku_row {
 }
 
 /// 指定したシフトをランダムにNに変える
+/// 指定したシフトがない場合は変更しない
 fn remove_random<R: Rng>(
     shift: Shift,
     schedule_config: &ScheduleConfig,
@@ -43,11 +44,17 @@ fn remove_random<R: Rng>(
             is.push(c);
         }
     }
+    // 候補がない場合は変更しない
+    // 数が変わる場合は後のチェックで戻される
+    if is.is_empty() {
+        return;
+    }
     let rnd = rng.gen_range(0..is.len());
     new_schedule[r][is[rnd]] = Shift::N;
 }
 
 /// ランダムなNを指定したシフトに変更する
+/// Nがない場合は変更しない
 fn add_random<R: Rng>(
     shift: Shift,
     schedule_config: &ScheduleConfig,
@@ -61,6 +68,11 @@ fn add_random<R: Rng>(
             is.push(c);
         }
     }
+    // 候補がない場合は変更しない
+    // 数が変わる場合は後のチェックで戻される
+    if is.is_empty() {
+        return;
+    }
     let rnd = rng.gen_range(0..is.len());
     new_schedule[r][is[rnd]] = shift;
 }
@@ -90,7 +102,7 @@ fn iak_renzoku(
 
 /// Iの後に来ないAをNで置き換える
 fn remove_improper_a(schedule_config: &ScheduleConfig, new_schedule: &mut Schedule, r: usize) {
-    for c in schedule_config.day.buffer_count..schedule_config.day.count {
+    for c in schedule_config.day.buffer_count.max(1)..schedule_config.day.count {
         if new_schedule[r][c] == Shift::A && new_schedule[r][c - 1] != Shift::I {
             new_schedule[r][c] = Shift::N;
         }
@@ -99,7 +111,7 @@ fn remove_improper_a(schedule_config: &ScheduleConfig, new_schedule: &mut Schedu
 
 /// Iの後にAがない場合、それをAにする
 fn add_proper_a(schedule_config: &ScheduleConfig, new_schedule: &mut Schedule, r: usize) {
-    for c in schedule_config.day.buffer_count..schedule_config.day.count {
+    for c in schedule_config.day.buffer_count.max(1)..schedule_config.day.count {
         if new_schedule[r][c] != Shift::A && new_schedule[r][c - 1] == Shift::I {
             new_schedule[r][c] = Shift::A;
         }
@@ -156,12 +168,12 @@ pub fn update_iak_safe<R: Rng>(
         let b3 = iak_renzoku(schedule_config, schedule, r, &1000.0)
             >= iak_renzoku(schedule_config, &new_schedule, r, &1000.0);
 
-        // Absoluteが変化していないか
+        // 変化した枠に入れられないシフトが入っていないか
         let b4 = {
             let mut ans = true;
             for c in schedule_config.day.buffer_count..schedule_config.day.count {
-                if schedule_state[r][c] == ShiftState::Absolute {
-                    ans = ans && schedule[r][c] == new_schedule[r][c];
+                if schedule[r][c] != new_schedule[r][c] {
+                    ans = ans && schedule_state[r][c].can_place(&new_schedule[r][c]);
                 }
             }
             ans
@@ -177,3 +189,33 @@ pub fn update_iak_safe<R: Rng>(
     }
     new_schedule
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ShiftState;
+
+    use rand::SeedableRng;
+
+    /// Kの入っていない行やNの入っていない行があっても、変更せずに続けるケース
+    #[test]
+    fn test_no_candidate() {
+        let schedule = vec![
+            vec![Shift::N, Shift::N, Shift::N, Shift::N],
+            vec![Shift::K, Shift::K, Shift::K, Shift::K],
+            vec![Shift::I, Shift::A, Shift::K, Shift::N],
+        ];
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.staff.count = 3;
+        schedule_config.day.count = 4;
+        let schedule_state = vec![vec![ShiftState::Random; 4]; 3];
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let next = update_iak_safe(&schedule_config, &schedule_state, &schedule, &mut rng);
+            assert_eq!(next[0], schedule[0]);
+            assert_eq!(next[1], schedule[1]);
+        }
+    }
+}
@@ -3,6 +3,7 @@ use super::super::{Schedule, ScheduleConfig, ScheduleState, Shift, ShiftState};
 use rand::Rng;
 
 /// ランダムな1つの枠をランダムな枠に変える
+/// 選んだシフトをその枠に入れられない場合は変えない
 pub fn update_iaknoh<R: Rng>(
     schedule_config: &ScheduleConfig,
     schedule_state: &ScheduleState,
@@ -13,8 +14,11 @@ pub fn update_iaknoh<R: Rng>(
     let rx: usize = rng.gen_range(0..schedule_config.staff.count);
     let ry: usize = rng.gen_range(schedule_config.day.buffer_count..schedule_config.day.count);
     if schedule_state[rx][ry] != ShiftState::Absolute {
-        new_schedule[rx][ry] =
+        let shift =
             [Shift::N, Shift::K, Shift::I, Shift::A, Shift::O, Shift::H][rng.gen_range(0..6)];
+        if schedule_state[rx][ry].can_place(&shift) {
+            new_schedule[rx][ry] = shift;
+        }
     }
     new_schedule
 }
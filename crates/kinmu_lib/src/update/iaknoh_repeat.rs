@@ -1,9 +1,11 @@
 use super::super::{Schedule, ScheduleConfig, ScheduleState, Shift, ShiftState};
+use super::MAX_TRIAL;
 
 use rand::Rng;
 
 /// ランダムな1つの枠をランダムな枠に変える
-/// Absoluteの場合繰り返す
+/// Absoluteの場合や、選んだシフトをその枠に入れられない場合選びなおす
+/// 一定回数選びなおしても見つからない場合は変更しない
 pub fn update_iaknoh_repeat<R: Rng>(
     schedule_config: &ScheduleConfig,
     schedule_state: &ScheduleState,
@@ -11,13 +13,17 @@ pub fn update_iaknoh_repeat<R: Rng>(
     rng: &mut R,
 ) -> Schedule {
     let mut new_schedule = schedule.clone();
-    let rx: usize = rng.gen_range(0..schedule_config.staff.count);
-    let ry: usize = rng.gen_range(schedule_config.day.buffer_count..schedule_config.day.count);
-    if schedule_state[rx][ry] != ShiftState::Absolute {
-        new_schedule[rx][ry] =
-            [Shift::N, Shift::K, Shift::I, Shift::A, Shift::O, Shift::H][rng.gen_range(0..6)];
-        new_schedule
-    } else {
-        update_iaknoh_repeat(schedule_config, schedule_state, schedule, rng)
+    for _ in 0..MAX_TRIAL {
+        let rx: usize = rng.gen_range(0..schedule_config.staff.count);
+        let ry: usize = rng.gen_range(schedule_config.day.buffer_count..schedule_config.day.count);
+        if schedule_state[rx][ry] != ShiftState::Absolute {
+            let shift =
+                [Shift::N, Shift::K, Shift::I, Shift::A, Shift::O, Shift::H][rng.gen_range(0..6)];
+            if schedule_state[rx][ry].can_place(&shift) {
+                new_schedule[rx][ry] = shift;
+                return new_schedule;
+            }
+        }
     }
+    new_schedule
 }
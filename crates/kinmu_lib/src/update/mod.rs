@@ -2,13 +2,17 @@
 
 use super::{DayState, Schedule, ScheduleConfig, Shift, ShiftState, StdScoreProp};
 
+use anyhow::Context;
+
 use kinmu_generator_with_annealing::Update;
+use kinmu_input_by_file::{unknown_function_error, CheckFunction, FromConfig, VecWrapper};
 
 use rand::Rng;
 
 mod iak_safe;
 mod iaknoh;
 mod iaknoh_repeat;
+mod moves;
 mod noh_repeat;
 mod range;
 mod range_or_iaknoh_repeat;
@@ -17,11 +21,50 @@ mod range_or_noh_repeat;
 use iak_safe::update_iak_safe;
 use iaknoh::update_iaknoh;
 use iaknoh_repeat::update_iaknoh_repeat;
+pub use moves::MoveKind;
+use moves::{neighbors_moves, update_moves};
 use noh_repeat::update_noh_repeat;
 use range::update_range;
 use range_or_iaknoh_repeat::update_range_or_iaknoh_repeat;
 use range_or_noh_repeat::update_range_or_noh_repeat;
 
+/// 変更できる枠を探す試行回数の上限
+const MAX_TRIAL: usize = 1000;
+
+/// update_noh_repeatで変更する枠に入っているシフト
+const NOHU: [Shift; 4] = [Shift::N, Shift::O, Shift::H, Shift::U];
+
+/// update_noh_repeatで入れるシフト
+const NOH: [Shift; 3] = [Shift::N, Shift::O, Shift::H];
+
+/// update_iaknoh_repeatで入れるシフト
+const IAKNOH: [Shift; 6] = [Shift::N, Shift::K, Shift::I, Shift::A, Shift::O, Shift::H];
+
+/// すべてのシフト
+const ALL_SHIFTS: [Shift; 9] = [
+    Shift::N,
+    Shift::K,
+    Shift::I,
+    Shift::A,
+    Shift::O,
+    Shift::H,
+    Shift::Y,
+    Shift::D,
+    Shift::U,
+];
+
+/// 登録されている更新関数の名前
+const UPDATE_NAMES: [&str; 8] = [
+    "update_iaknoh_repeat",
+    "update_iaknoh",
+    "update_noh_repeat",
+    "update_iak_safe",
+    "update_range",
+    "update_range_or_noh_repeat",
+    "update_range_or_iaknoh_repeat",
+    "update_moves",
+];
+
 /// 生成器で用いる更新関数のための型
 /// GeneratorWithAnnealingのUpdateを実装
 #[derive(Debug, Clone)]
@@ -29,38 +72,465 @@ pub struct StdUpdate;
 
 /// Updateの実装
 impl Update<StdScoreProp, Shift, ShiftState, DayState> for StdUpdate {
-    fn generate<'a, R: Rng>(
+    fn generate<'a, R: Rng + 'a>(
         &self,
         name: &str,
         schedule_config: &'a ScheduleConfig,
     ) -> anyhow::Result<Box<dyn FnMut(&Schedule, &mut R) -> Schedule + 'a>> {
         let schedule_state = &schedule_config.day.schedule_states;
         match name {
-            "update_iaknoh_repeat" => Ok(Box::new(move |schedule, rng| {
-                update_iaknoh_repeat(schedule_config, schedule_state, schedule, rng)
-            })),
-            "update_iaknoh" => Ok(Box::new(move |schedule, rng| {
-                update_iaknoh(schedule_config, schedule_state, schedule, rng)
-            })),
-            "update_noh_repeat" => Ok(Box::new(move |schedule, rng| {
-                update_noh_repeat(schedule_config, schedule_state, schedule, rng)
-            })),
-            "update_iak_safe" => Ok(Box::new(move |schedule, rng| {
-                update_iak_safe(schedule_config, schedule_state, schedule, rng)
-            })),
-            "update_range" => Ok(Box::new(move |schedule, rng| {
-                update_range(schedule_config, schedule_state, schedule, rng)
-            })),
-            "update_range_or_noh_repeat" => Ok(Box::new(move |schedule, rng| {
-                update_range_or_noh_repeat(schedule_config, schedule_state, schedule, rng)
-            })),
-            "update_range_or_iaknoh_repeat" => Ok(Box::new(move |schedule, rng| {
-                update_range_or_iaknoh_repeat(schedule_config, schedule_state, schedule, rng)
-            })),
-            _ => Err(anyhow::anyhow!(
-                "Failed to generate update function {}",
-                name
-            )),
+            "update_iaknoh_repeat" => {
+                check_placeable(schedule_config, &ALL_SHIFTS, &IAKNOH)
+                    .with_context(|| format!("Failed to generate {}", name))?;
+                Ok(Box::new(move |schedule, rng| {
+                    update_iaknoh_repeat(schedule_config, schedule_state, schedule, rng)
+                }))
+            }
+            "update_iaknoh" => {
+                check_cells(schedule_config)
+                    .with_context(|| format!("Failed to generate {}", name))?;
+                Ok(Box::new(move |schedule, rng| {
+                    update_iaknoh(schedule_config, schedule_state, schedule, rng)
+                }))
+            }
+            "update_noh_repeat" => {
+                check_placeable(schedule_config, &NOHU, &NOH)
+                    .with_context(|| format!("Failed to generate {}", name))?;
+                Ok(Box::new(move |schedule, rng| {
+                    update_noh_repeat(schedule_config, schedule_state, schedule, rng)
+                }))
+            }
+            "update_iak_safe" => {
+                check_cells(schedule_config)
+                    .with_context(|| format!("Failed to generate {}", name))?;
+                Ok(Box::new(move |schedule, rng| {
+                    update_iak_safe(schedule_config, schedule_state, schedule, rng)
+                }))
+            }
+            "update_range" => {
+                check_cells(schedule_config)
+                    .with_context(|| format!("Failed to generate {}", name))?;
+                Ok(Box::new(move |schedule, rng| {
+                    update_range(schedule_config, schedule_state, schedule, rng)
+                }))
+            }
+            "update_range_or_noh_repeat" => {
+                check_placeable(schedule_config, &NOHU, &NOH)
+                    .with_context(|| format!("Failed to generate {}", name))?;
+                Ok(Box::new(move |schedule, rng| {
+                    update_range_or_noh_repeat(schedule_config, schedule_state, schedule, rng)
+                }))
+            }
+            "update_range_or_iaknoh_repeat" => {
+                check_placeable(schedule_config, &ALL_SHIFTS, &IAKNOH)
+                    .with_context(|| format!("Failed to generate {}", name))?;
+                Ok(Box::new(move |schedule, rng| {
+                    update_range_or_iaknoh_repeat(schedule_config, schedule_state, schedule, rng)
+                }))
+            }
+            _ if name.trim_start().starts_with('[') => {
+                let (weights, mut funcs): (Vec<_>, Vec<_>) = self
+                    .generate_operators(name, schedule_config)?
+                    .into_iter()
+                    .map(|(_, w, f)| (w, f))
+                    .unzip();
+                Ok(Box::new(move |schedule, rng| {
+                    let i = choose_weighted(&weights, rng);
+                    funcs[i](schedule, rng)
+                }))
+            }
+            _ => match name.split_once(' ') {
+                Some(("update_moves", p)) => {
+                    let (shifts, moves) = parse_moves(name, p, schedule_config)?;
+                    let (kinds, weights): (Vec<_>, Vec<_>) = moves.into_iter().unzip();
+                    Ok(Box::new(move |schedule, rng| {
+                        update_moves(
+                            schedule_config,
+                            schedule_state,
+                            &shifts,
+                            &kinds,
+                            &weights,
+                            schedule,
+                            rng,
+                        )
+                    }))
+                }
+                _ => Err(unknown_function_error(name, &UPDATE_NAMES)),
+            },
+        }
+    }
+
+    /// 重み付きの組み合わせの場合、組み合わせた更新関数をそれぞれ生成する
+    fn generate_operators<'a, R: Rng + 'a>(
+        &self,
+        name: &str,
+        schedule_config: &'a ScheduleConfig,
+    ) -> anyhow::Result<
+        Vec<(
+            String,
+            f32,
+            Box<dyn FnMut(&Schedule, &mut R) -> Schedule + 'a>,
+        )>,
+    > {
+        if !name.trim_start().starts_with('[') {
+            return Ok(vec![(
+                name.to_string(),
+                1.0,
+                self.generate(name, schedule_config)?,
+            )]);
+        }
+        let VecWrapper(weighted) = <VecWrapper<(String, f32)>>::from_config(name)
+            .with_context(|| format!("Failed to parse {}", name))?;
+        check_weights(&weighted.iter().map(|(_, w)| *w).collect::<Vec<_>>())
+            .with_context(|| format!("Failed to generate {}", name))?;
+        weighted
+            .into_iter()
+            .map(|(n, w)| {
+                let f = self.generate(&n, schedule_config)?;
+                Ok((n, w, f))
+            })
+            .collect()
+    }
+
+    /// 更新関数の近傍を列挙する
+    /// 重み付きの組み合わせの場合は、組み合わせた更新関数の近傍をすべて列挙する
+    /// update_moves以外の更新関数は、named_movesで対応する操作の近傍を列挙する
+    fn generate_neighborhood<'a>(
+        &self,
+        name: &str,
+        schedule_config: &'a ScheduleConfig,
+    ) -> anyhow::Result<Box<dyn Fn(&Schedule) -> Vec<Vec<(usize, usize, Shift)>> + 'a>> {
+        let schedule_state = &schedule_config.day.schedule_states;
+        if name.trim_start().starts_with('[') {
+            let VecWrapper(weighted) = <VecWrapper<(String, f32)>>::from_config(name)
+                .with_context(|| format!("Failed to parse {}", name))?;
+            let neighborhoods = weighted
+                .iter()
+                .map(|(n, _)| self.generate_neighborhood(n, schedule_config))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            return Ok(Box::new(move |schedule| {
+                neighborhoods.iter().flat_map(|f| f(schedule)).collect()
+            }));
+        }
+        match name.split_once(' ') {
+            Some(("update_moves", p)) => {
+                let (shifts, moves) = parse_moves(name, p, schedule_config)?;
+                Ok(Box::new(move |schedule| {
+                    neighbors_moves(schedule_config, schedule_state, &shifts, &moves, schedule)
+                }))
+            }
+            _ => match named_moves(name.trim()) {
+                Some(moves) => {
+                    // 生成できない場合のエラーは更新関数と同じにする
+                    self.check_function(name.trim(), schedule_config)?;
+                    Ok(Box::new(move |schedule| {
+                        moves
+                            .iter()
+                            .flat_map(|(shifts, kind)| {
+                                neighbors_moves(
+                                    schedule_config,
+                                    schedule_state,
+                                    shifts,
+                                    &[(*kind, 1.0)],
+                                    schedule,
+                                )
+                            })
+                            .collect()
+                    }))
+                }
+                None if UPDATE_NAMES.contains(&name.trim()) => Err(anyhow::anyhow!(
+                    "更新関数{}の近傍は列挙できません\nヒント: stage_typeがpolishの段階ではupdate_iak_safe以外の更新関数を指定してください",
+                    name
+                )),
+                None => Err(unknown_function_error(name, &UPDATE_NAMES)),
+            },
+        }
+    }
+}
+
+/// 更新関数の近傍として列挙する、シフトの集合と操作の組
+/// update_rangeは、同じ日付か同じ職員の1つの枠同士の入れ替えのみを列挙する
+/// update_iak_safeとupdate_moves、登録されていない名前の場合はNone
+fn named_moves(name: &str) -> Option<Vec<(&'static [Shift], MoveKind)>> {
+    let change: Option<&'static [Shift]> = match name {
+        "update_iaknoh_repeat" | "update_iaknoh" | "update_range_or_iaknoh_repeat" => Some(&IAKNOH),
+        "update_noh_repeat" | "update_range_or_noh_repeat" => Some(&NOH),
+        _ => None,
+    };
+    let range = matches!(
+        name,
+        "update_range" | "update_range_or_noh_repeat" | "update_range_or_iaknoh_repeat"
+    );
+    if change.is_none() && !range {
+        return None;
+    }
+    let mut moves = Vec::new();
+    if let Some(shifts) = change {
+        moves.push((shifts, MoveKind::Change));
+    }
+    if range {
+        moves.push((&ALL_SHIFTS[..], MoveKind::SwapDay));
+        moves.push((&ALL_SHIFTS[..], MoveKind::SwapStaff));
+    }
+    Some(moves)
+}
+
+/// update_movesのパラメータを読み込み、正常か判定する
+#[allow(clippy::type_complexity)]
+fn parse_moves(
+    name: &str,
+    p: &str,
+    schedule_config: &ScheduleConfig,
+) -> anyhow::Result<(Vec<Shift>, Vec<(MoveKind, f32)>)> {
+    let (VecWrapper(shifts), VecWrapper(moves)) =
+        <(VecWrapper<Shift>, VecWrapper<(MoveKind, f32)>)>::from_config(p)
+            .with_context(|| format!("Failed to parse {}", name))?;
+    check_moves(schedule_config, &shifts, &moves)
+        .with_context(|| format!("Failed to generate {}", name))?;
+    Ok((shifts, moves))
+}
+
+/// 更新関数の名前とパラメータが正常か判定
+/// 実際に生成できるかで判定する
+impl CheckFunction<StdScoreProp, Shift, ShiftState, DayState> for StdUpdate {
+    fn check_function(&self, name: &str, schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+        self.generate::<rand::rngs::StdRng>(name, schedule_config)
+            .map(|_| ())
+    }
+
+    fn check_neighborhood_function(
+        &self,
+        name: &str,
+        schedule_config: &ScheduleConfig,
+    ) -> anyhow::Result<()> {
+        self.generate_neighborhood(name, schedule_config)
+            .map(|_| ())
+    }
+}
+
+/// update_movesのパラメータが正常か
+fn check_moves(
+    schedule_config: &ScheduleConfig,
+    shifts: &[Shift],
+    moves: &[(MoveKind, f32)],
+) -> anyhow::Result<()> {
+    anyhow::ensure!(!shifts.is_empty(), "シフトが指定されていません");
+    anyhow::ensure!(!moves.is_empty(), "操作が指定されていません");
+    check_weights(&moves.iter().map(|(_, w)| *w).collect::<Vec<_>>())?;
+    check_cells(schedule_config)
+}
+
+/// スタッフとバッファ以外の日がそれぞれ1つ以上あるか
+fn check_cells(schedule_config: &ScheduleConfig) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        0 < schedule_config.staff.count
+            && schedule_config.day.buffer_count < schedule_config.day.count,
+        "更新できる枠がありません"
+    );
+    Ok(())
+}
+
+/// fromのいずれかを入れられる枠で、toの別のシフトも入れられる枠があるか
+/// 更新関数の生成時には表が埋まっていないため、枠の状態のみを確認し、実際に入っているシフトは確認しない
+/// ない場合、更新関数は表を変更できない
+fn check_placeable(
+    schedule_config: &ScheduleConfig,
+    from: &[Shift],
+    to: &[Shift],
+) -> anyhow::Result<()> {
+    let exists = (0..schedule_config.staff.count).any(|r| {
+        (schedule_config.day.buffer_count..schedule_config.day.count).any(|c| {
+            let state = &schedule_config.day.schedule_states[r][c];
+            from.iter()
+                .any(|f| state.can_place(f) && to.iter().any(|t| t != f && state.can_place(t)))
+        })
+    });
+    anyhow::ensure!(
+        exists,
+        "{}のいずれかが入る枠で、{}の別のシフトに変えられる枠がありません",
+        join_shifts(from),
+        join_shifts(to)
+    );
+    Ok(())
+}
+
+/// シフトのリストをカンマ区切りの文字列にする
+fn join_shifts(shifts: &[Shift]) -> String {
+    shifts
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 重みが正常か
+fn check_weights(weights: &[f32]) -> anyhow::Result<()> {
+    anyhow::ensure!(!weights.is_empty(), "重みが指定されていません");
+    anyhow::ensure!(
+        weights.iter().all(|w| 0.0 <= *w),
+        "重みに負の値が指定されています"
+    );
+    anyhow::ensure!(0.0 < weights.iter().sum::<f32>(), "重みの合計が0です");
+    Ok(())
+}
+
+/// 重みに従ってランダムに添字を選ぶ
+fn choose_weighted<R: Rng>(weights: &[f32], rng: &mut R) -> usize {
+    let total: f32 = weights.iter().sum();
+    let mut r = rng.gen_range(0.0..total);
+    for (i, w) in weights.iter().enumerate() {
+        if r < *w {
+            return i;
+        }
+        r -= w;
+    }
+    weights.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+
+    /// 重みが0の添字は選ばれず、重みの比に従って選ばれるケース
+    #[test]
+    fn test_choose_weighted() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut counts = [0; 3];
+        for _ in 0..10000 {
+            counts[choose_weighted(&[1.0, 0.0, 3.0], &mut rng)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((2000..3000).contains(&counts[0]));
+    }
+
+    /// 重み付きの組み合わせで、重みが0の更新関数は用いられないケース
+    /// update_movesのChangeはNとKのみ、update_rangeはそれ以外も入れる
+    #[test]
+    fn test_weighted_dispatch() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.staff.count = 2;
+        schedule_config.day.count = 4;
+        schedule_config.day.schedule_states = vec![vec![ShiftState::Random; 4]; 2];
+        let schedule = vec![vec![Shift::N; 4]; 2];
+
+        let mut update = StdUpdate
+            .generate::<rand::rngs::StdRng>(
+                "[(update_range, 0), (update_moves ([N, K], [(Change, 1)]), 1)]",
+                &schedule_config,
+            )
+            .unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let next = update(&schedule, &mut rng);
+            assert!(next
+                .iter()
+                .flatten()
+                .all(|s| [Shift::N, Shift::K].contains(s)));
+            assert_eq!(next.iter().flatten().filter(|s| **s == Shift::K).count(), 1);
+        }
+
+        assert!(StdUpdate
+            .generate::<rand::rngs::StdRng>("[(update_range, 0)]", &schedule_config)
+            .is_err());
+    }
+
+    /// 変更できる枠がない場合、生成時にエラーになるケース
+    #[test]
+    fn test_check_placeable() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.staff.count = 1;
+        schedule_config.day.count = 2;
+
+        // 全てAbsolute
+        schedule_config.day.schedule_states = vec![vec![ShiftState::Absolute; 2]];
+        assert!(check_placeable(&schedule_config, &NOHU, &NOH).is_err());
+
+        // Nにしかできない枠はN,O,Hの間で変えられない
+        schedule_config.day.schedule_states = vec![vec![ShiftState::OneOf(vec![Shift::N]); 2]];
+        assert!(check_placeable(&schedule_config, &NOHU, &NOH).is_err());
+        assert!(StdUpdate
+            .generate::<rand::rngs::StdRng>("update_noh_repeat", &schedule_config)
+            .is_err());
+        assert!(StdUpdate
+            .generate::<rand::rngs::StdRng>("update_iaknoh_repeat", &schedule_config)
+            .is_err());
+
+        // N,O,Hを入れられない枠は変更されない
+        schedule_config.day.schedule_states =
+            vec![vec![ShiftState::OneOf(vec![Shift::K, Shift::I]); 2]];
+        assert!(check_placeable(&schedule_config, &NOHU, &NOH).is_err());
+        assert!(check_placeable(&schedule_config, &ALL_SHIFTS, &IAKNOH).is_ok());
+
+        schedule_config.day.schedule_states = vec![vec![
+            ShiftState::Absolute,
+            ShiftState::OneOf(vec![Shift::N, Shift::O]),
+        ]];
+        assert!(check_placeable(&schedule_config, &NOHU, &NOH).is_ok());
+    }
+
+    /// update_moves以外の更新関数の近傍を列挙するケース
+    /// update_iak_safeの近傍は列挙できない
+    #[test]
+    fn test_named_neighborhood() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.staff.count = 2;
+        schedule_config.day.count = 2;
+        schedule_config.day.schedule_states = vec![vec![ShiftState::Random; 2]; 2];
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, O], vec![H, K]]
+        };
+
+        for (name, count) in [
+            // N,O,Hの3つの枠をそれぞれ別の2つのシフトに変える
+            ("update_noh_repeat", 6),
+            // 同じ日付の入れ替えが2つ、同じ職員の入れ替えが2つ
+            ("update_range", 4),
+            ("update_range_or_noh_repeat", 10),
+            ("[(update_noh_repeat, 1), (update_range, 1)]", 10),
+        ] {
+            let neighborhood = StdUpdate
+                .generate_neighborhood(name, &schedule_config)
+                .unwrap();
+            assert_eq!(neighborhood(&schedule).len(), count, "{}", name);
+        }
+
+        assert!(StdUpdate
+            .generate_neighborhood("update_iak_safe", &schedule_config)
+            .is_err());
+        assert!(StdUpdate
+            .generate_neighborhood("update_unknown", &schedule_config)
+            .is_err());
+    }
+
+    /// スタッフや日がない場合、生成時にエラーになるケース
+    #[test]
+    fn test_check_cells() {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.day.count = 2;
+        schedule_config.day.buffer_count = 1;
+        for name in ["update_iaknoh", "update_range", "update_iak_safe"] {
+            assert!(StdUpdate
+                .generate::<rand::rngs::StdRng>(name, &schedule_config)
+                .is_err());
+        }
+
+        schedule_config.staff.count = 1;
+        schedule_config.day.buffer_count = 2;
+        for name in ["update_iaknoh", "update_range", "update_iak_safe"] {
+            assert!(StdUpdate
+                .generate::<rand::rngs::StdRng>(name, &schedule_config)
+                .is_err());
+        }
+
+        schedule_config.day.buffer_count = 1;
+        schedule_config.day.schedule_states = vec![vec![ShiftState::Random; 2]];
+        for name in ["update_iaknoh", "update_range", "update_iak_safe"] {
+            assert!(StdUpdate
+                .generate::<rand::rngs::StdRng>(name, &schedule_config)
+                .is_ok());
         }
     }
 }
@@ -0,0 +1,391 @@
+//! 指定したシフトの集合と操作の種類から更新を行うupdate_movesのモジュール
+//!
+//! ### 操作の種類
+//! - Change: ランダムな1つの枠を指定したシフトのうちランダムなものに変える
+//! - SwapDay: 同じ日付の2人の職員の枠を入れ替える
+//! - SwapStaff: 同じ職員の2つの日付の枠を入れ替える
+//! - Block: 2人の職員の連続する2~3日分の範囲を入れ替える
+//!
+//! いずれの操作も、指定したシフトの集合に含まれるシフトが入っている枠のみを変更する
+//! 変更できない枠を選んだ場合は選びなおし、一定回数選びなおしても見つからない場合は変更しない
+//! 局所探索では、指定した操作で変更できるすべての近傍を列挙する
+
+use super::{
+    super::{Schedule, ScheduleConfig, ScheduleState, Shift},
+    choose_weighted, MAX_TRIAL,
+};
+
+use kinmu_input_by_file::FromConfig;
+
+use rand::Rng;
+
+/// update_movesで用いる操作の種類
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveKind {
+    Change,
+    SwapDay,
+    SwapStaff,
+    Block,
+}
+
+impl FromConfig for MoveKind {
+    fn from_config(s: &str) -> anyhow::Result<Self> {
+        match s.trim() {
+            "Change" => Ok(MoveKind::Change),
+            "SwapDay" => Ok(MoveKind::SwapDay),
+            "SwapStaff" => Ok(MoveKind::SwapStaff),
+            "Block" => Ok(MoveKind::Block),
+            _ => Err(anyhow::anyhow!("Failed to parse MoveKind: {}", s)),
+        }
+    }
+}
+
+/// 2つの枠を入れ替えられるか
+/// どちらも指定したシフトの集合に含まれ、異なるシフトで、入れ替え後のシフトを入れてよい必要がある
+fn can_swap(
+    shifts: &[Shift],
+    schedule_state: &ScheduleState,
+    schedule: &Schedule,
+    (r1, c1): (usize, usize),
+    (r2, c2): (usize, usize),
+) -> bool {
+    let s1 = schedule[r1][c1];
+    let s2 = schedule[r2][c2];
+    s1 != s2
+        && shifts.contains(&s1)
+        && shifts.contains(&s2)
+        && schedule_state[r1][c1].can_place(&s2)
+        && schedule_state[r2][c2].can_place(&s1)
+}
+
+/// 指定したシフトの集合と、操作の種類とその重みから、ランダムに選んだ操作で更新する
+/// kindsとweightsは同じ長さである必要がある
+pub fn update_moves<R: Rng>(
+    schedule_config: &ScheduleConfig,
+    schedule_state: &ScheduleState,
+    shifts: &[Shift],
+    kinds: &[MoveKind],
+    weights: &[f32],
+    schedule: &Schedule,
+    rng: &mut R,
+) -> Schedule {
+    let mut new_schedule = schedule.clone();
+    let staff_count = schedule_config.staff.count;
+    let day_range = schedule_config.day.buffer_count..schedule_config.day.count;
+    let kind = kinds[choose_weighted(weights, rng)];
+    for _ in 0..MAX_TRIAL {
+        match kind {
+            MoveKind::Change => {
+                let r = rng.gen_range(0..staff_count);
+                let c = rng.gen_range(day_range.clone());
+                let shift = shifts[rng.gen_range(0..shifts.len())];
+                if schedule[r][c] != shift
+                    && shifts.contains(&schedule[r][c])
+                    && schedule_state[r][c].can_place(&shift)
+                {
+                    new_schedule[r][c] = shift;
+                    return new_schedule;
+                }
+            }
+            MoveKind::SwapDay => {
+                let r1 = rng.gen_range(0..staff_count);
+                let r2 = rng.gen_range(0..staff_count);
+                let c = rng.gen_range(day_range.clone());
+                if can_swap(shifts, schedule_state, schedule, (r1, c), (r2, c)) {
+                    new_schedule[r1][c] = schedule[r2][c];
+                    new_schedule[r2][c] = schedule[r1][c];
+                    return new_schedule;
+                }
+            }
+            MoveKind::SwapStaff => {
+                let r = rng.gen_range(0..staff_count);
+                let c1 = rng.gen_range(day_range.clone());
+                let c2 = rng.gen_range(day_range.clone());
+                if can_swap(shifts, schedule_state, schedule, (r, c1), (r, c2)) {
+                    new_schedule[r][c1] = schedule[r][c2];
+                    new_schedule[r][c2] = schedule[r][c1];
+                    return new_schedule;
+                }
+            }
+            MoveKind::Block => {
+                let range_size = rng.gen_range(2..4).min(day_range.len());
+                let r1 = rng.gen_range(0..staff_count);
+                let r2 = rng.gen_range(0..staff_count);
+                let begin = rng.gen_range(day_range.start..(day_range.end - range_size + 1));
+                let mut changed = false;
+                let mut ok = true;
+                for c in begin..(begin + range_size) {
+                    if schedule[r1][c] != schedule[r2][c] {
+                        changed = true;
+                        ok = ok && can_swap(shifts, schedule_state, schedule, (r1, c), (r2, c));
+                    }
+                }
+                if changed && ok {
+                    for c in begin..(begin + range_size) {
+                        new_schedule[r1][c] = schedule[r2][c];
+                        new_schedule[r2][c] = schedule[r1][c];
+                    }
+                    return new_schedule;
+                }
+            }
+        }
+    }
+    new_schedule
+}
+
+/// 指定したシフトの集合と操作の種類で変更できる近傍をすべて列挙する
+/// 操作の重みは用いない
+pub fn neighbors_moves(
+    schedule_config: &ScheduleConfig,
+    schedule_state: &ScheduleState,
+    shifts: &[Shift],
+    moves: &[(MoveKind, f32)],
+    schedule: &Schedule,
+) -> Vec<Vec<(usize, usize, Shift)>> {
+    let staff_count = schedule_config.staff.count;
+    let day_range = schedule_config.day.buffer_count..schedule_config.day.count;
+    let mut kinds = Vec::new();
+    for (kind, _) in moves {
+        if !kinds.contains(kind) {
+            kinds.push(*kind);
+        }
+    }
+    let mut neighbors = Vec::new();
+    for kind in kinds {
+        match kind {
+            MoveKind::Change => {
+                for r in 0..staff_count {
+                    for c in day_range.clone() {
+                        if !shifts.contains(&schedule[r][c]) {
+                            continue;
+                        }
+                        for &shift in shifts {
+                            if schedule[r][c] != shift && schedule_state[r][c].can_place(&shift) {
+                                neighbors.push(vec![(r, c, shift)]);
+                            }
+                        }
+                    }
+                }
+            }
+            MoveKind::SwapDay => {
+                for c in day_range.clone() {
+                    for r1 in 0..staff_count {
+                        for r2 in (r1 + 1)..staff_count {
+                            if can_swap(shifts, schedule_state, schedule, (r1, c), (r2, c)) {
+                                neighbors
+                                    .push(vec![(r1, c, schedule[r2][c]), (r2, c, schedule[r1][c])]);
+                            }
+                        }
+                    }
+                }
+            }
+            MoveKind::SwapStaff => {
+                for r in 0..staff_count {
+                    for c1 in day_range.clone() {
+                        for c2 in (c1 + 1)..day_range.end {
+                            if can_swap(shifts, schedule_state, schedule, (r, c1), (r, c2)) {
+                                neighbors
+                                    .push(vec![(r, c1, schedule[r][c2]), (r, c2, schedule[r][c1])]);
+                            }
+                        }
+                    }
+                }
+            }
+            MoveKind::Block => {
+                for range_size in 2..4 {
+                    if day_range.len() < range_size {
+                        break;
+                    }
+                    for begin in day_range.start..(day_range.end - range_size + 1) {
+                        for r1 in 0..staff_count {
+                            for r2 in (r1 + 1)..staff_count {
+                                let cs = (begin..(begin + range_size))
+                                    .filter(|&c| schedule[r1][c] != schedule[r2][c])
+                                    .collect::<Vec<_>>();
+                                if !cs.is_empty()
+                                    && cs.iter().all(|&c| {
+                                        can_swap(shifts, schedule_state, schedule, (r1, c), (r2, c))
+                                    })
+                                {
+                                    neighbors.push(
+                                        cs.iter()
+                                            .flat_map(|&c| {
+                                                [(r1, c, schedule[r2][c]), (r2, c, schedule[r1][c])]
+                                            })
+                                            .collect(),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    neighbors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ShiftState;
+
+    use rand::SeedableRng;
+
+    /// 全ての枠がRandomなScheduleConfigを作る
+    fn config(schedule: &Schedule) -> ScheduleConfig {
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.staff.count = schedule.len();
+        schedule_config.day.count = schedule[0].len();
+        schedule_config.day.schedule_states =
+            vec![vec![ShiftState::Random; schedule[0].len()]; schedule.len()];
+        schedule_config
+    }
+
+    /// 変更された枠の数
+    fn diff_count(a: &Schedule, b: &Schedule) -> usize {
+        a.iter()
+            .flatten()
+            .zip(b.iter().flatten())
+            .filter(|(x, y)| x != y)
+            .count()
+    }
+
+    /// Changeは指定したシフトの枠を1つだけ、指定したシフトに変えるケース
+    #[test]
+    fn test_change() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K, I, A], vec![K, N, N, O]]
+        };
+        let schedule_config = config(&schedule);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let next = update_moves(
+                &schedule_config,
+                &schedule_config.day.schedule_states,
+                &[Shift::N, Shift::K],
+                &[MoveKind::Change],
+                &[1.0],
+                &schedule,
+                &mut rng,
+            );
+            assert_eq!(diff_count(&schedule, &next), 1);
+            for (row, next_row) in schedule.iter().zip(&next) {
+                for (x, y) in row.iter().zip(next_row) {
+                    if x != y {
+                        assert!([Shift::N, Shift::K].contains(x));
+                        assert!([Shift::N, Shift::K].contains(y));
+                    }
+                }
+            }
+        }
+    }
+
+    /// 変更できる枠がない場合、選びなおしを打ち切って変更しないケース
+    #[test]
+    fn test_no_candidate() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![I, A, K], vec![I, A, K]]
+        };
+        let schedule_config = config(&schedule);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let next = update_moves(
+            &schedule_config,
+            &schedule_config.day.schedule_states,
+            &[Shift::N, Shift::O],
+            &[
+                MoveKind::Change,
+                MoveKind::SwapDay,
+                MoveKind::SwapStaff,
+                MoveKind::Block,
+            ],
+            &[1.0, 1.0, 1.0, 1.0],
+            &schedule,
+            &mut rng,
+        );
+        assert_eq!(schedule, next);
+    }
+
+    /// 重みが0の操作は選ばれないケース
+    /// SwapDayで入れ替えた場合、各日付のシフトの組は変わらない
+    #[test]
+    fn test_weights() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K, N], vec![K, N, K]]
+        };
+        let schedule_config = config(&schedule);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let next = update_moves(
+                &schedule_config,
+                &schedule_config.day.schedule_states,
+                &[Shift::N, Shift::K],
+                &[MoveKind::Change, MoveKind::SwapDay],
+                &[0.0, 1.0],
+                &schedule,
+                &mut rng,
+            );
+            assert_eq!(diff_count(&schedule, &next), 2);
+            assert!(next[0].iter().zip(&next[1]).all(|(x, y)| x != y));
+        }
+    }
+
+    /// 列挙する近傍の数と、Absoluteの枠を変更しないことを確かめるケース
+    #[test]
+    fn test_neighbors() {
+        let schedule = {
+            use Shift::*;
+            vec![vec![N, K], vec![K, N]]
+        };
+        let mut schedule_config = config(&schedule);
+        let shifts = [Shift::N, Shift::K];
+
+        // Changeは4枠それぞれ1通り、SwapDayは2日それぞれ1通り、SwapStaffは2人それぞれ1通り
+        let moves = [
+            (MoveKind::Change, 1.0),
+            (MoveKind::SwapDay, 1.0),
+            (MoveKind::SwapStaff, 1.0),
+        ];
+        let ns = neighbors_moves(
+            &schedule_config,
+            &schedule_config.day.schedule_states,
+            &shifts,
+            &moves,
+            &schedule,
+        );
+        assert_eq!(ns.len(), 8);
+
+        // Blockは2日分の範囲を入れ替える1通り
+        let ns = neighbors_moves(
+            &schedule_config,
+            &schedule_config.day.schedule_states,
+            &shifts,
+            &[(MoveKind::Block, 1.0)],
+            &schedule,
+        );
+        assert_eq!(
+            ns,
+            vec![vec![
+                (0, 0, Shift::K),
+                (1, 0, Shift::N),
+                (0, 1, Shift::N),
+                (1, 1, Shift::K)
+            ]]
+        );
+
+        schedule_config.day.schedule_states[0][0] = ShiftState::Absolute;
+        let ns = neighbors_moves(
+            &schedule_config,
+            &schedule_config.day.schedule_states,
+            &shifts,
+            &moves,
+            &schedule,
+        );
+        assert!(ns.iter().flatten().all(|&(r, c, _)| (r, c) != (0, 0)));
+        assert_eq!(ns.len(), 5);
+    }
+}
@@ -1,8 +1,9 @@
-use super::super::{Schedule, ScheduleConfig, ScheduleState, ShiftState};
+use super::super::{Schedule, ScheduleConfig, ScheduleState};
 
 use rand::Rng;
 
 /// ランダムなスタッフ同士の連続する1~3日分の範囲を入れ替える
+/// 入れ替えたシフトをその枠に入れられない場合は入れ替えない
 pub fn update_range<R: Rng>(
     schedule_config: &ScheduleConfig,
     schedule_state: &ScheduleState,
@@ -10,7 +11,8 @@ pub fn update_range<R: Rng>(
     rng: &mut R,
 ) -> Schedule {
     let mut new_schedule = schedule.clone();
-    let range_size = rng.gen_range(1..3);
+    // 日数が範囲より短い場合は日数分だけ入れ替える
+    let range_size = rng.gen_range(1..3).min(schedule_config.day.count);
 
     let staff1 = rng.gen_range(0..schedule_config.staff.count);
     let staff2 = rng.gen_range(0..schedule_config.staff.count);
@@ -25,12 +27,14 @@ pub fn update_range<R: Rng>(
         new_schedule[staff1][day1_begin + dd] = schedule[staff2][day2_begin + dd];
         new_schedule[staff2][day2_begin + dd] = schedule[staff1][day1_begin + dd];
 
-        // Absoluteかつ一致しないなら
-        let abs1 = schedule_state[staff1][day1_begin + dd] == ShiftState::Absolute;
-        let abs2 = schedule_state[staff2][day2_begin + dd] == ShiftState::Absolute;
-        let same1 = new_schedule[staff1][day1_begin + dd] == schedule[staff1][day1_begin + dd];
-        let same2 = new_schedule[staff1][day1_begin + dd] == schedule[staff1][day1_begin + dd];
-        if (abs1 && !same1) || (abs2 && !same2) {
+        // 変化した枠に入れられないシフトが入るなら
+        let ok1 = new_schedule[staff1][day1_begin + dd] == schedule[staff1][day1_begin + dd]
+            || schedule_state[staff1][day1_begin + dd]
+                .can_place(&new_schedule[staff1][day1_begin + dd]);
+        let ok2 = new_schedule[staff2][day2_begin + dd] == schedule[staff2][day2_begin + dd]
+            || schedule_state[staff2][day2_begin + dd]
+                .can_place(&new_schedule[staff2][day2_begin + dd]);
+        if !(ok1 && ok2) {
             cancel = true;
         }
     }
@@ -41,3 +45,28 @@ pub fn update_range<R: Rng>(
         new_schedule
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Shift, ShiftState};
+
+    use rand::SeedableRng;
+
+    /// 日数が入れ替える範囲より短くても入れ替えられるケース
+    #[test]
+    fn test_short_day() {
+        let schedule = vec![vec![Shift::N], vec![Shift::K]];
+        let mut schedule_config: ScheduleConfig = Default::default();
+        schedule_config.staff.count = 2;
+        schedule_config.day.count = 1;
+        let schedule_state = vec![vec![ShiftState::Random; 1]; 2];
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let next = update_range(&schedule_config, &schedule_state, &schedule, &mut rng);
+            assert!(next == schedule || next == vec![vec![Shift::K], vec![Shift::N]]);
+        }
+    }
+}
@@ -49,6 +49,18 @@ pub fn derive_score_prop(input: DeriveInput) -> syn::Result<proc_macro2::TokenSt
                     #(Self::#variants(x) => #trait_path::eval_immut(x, staff_config, day_config, schedule),)*
                 }
             }
+
+            fn lower_bound(&self, staff_config: &kinmu_model::StaffConfig, day_config: &kinmu_model::DayConfig<#shift, #shift_state, #day_state>) -> kinmu_model::Score {
+                match self {
+                    #(Self::#variants(x) => #trait_path::lower_bound(x, staff_config, day_config),)*
+                }
+            }
+
+            fn diagnose(&self, staff_config: &kinmu_model::StaffConfig, day_config: &kinmu_model::DayConfig<#shift, #shift_state, #day_state>) -> Vec<String> {
+                match self {
+                    #(Self::#variants(x) => #trait_path::diagnose(x, staff_config, day_config),)*
+                }
+            }
         }
     };
 
@@ -1,12 +1,106 @@
 use super::config::ScheduleConfig;
-use super::Schedule;
+use super::{Schedule, Score};
 
+use std::fmt;
 use std::time::Duration;
 
 /// Generatorで出力され、Outputに渡される結果の型
 #[derive(Debug, Clone)]
 pub struct Answer<SP, S, SS, DS> {
-    pub models: Vec<Schedule<S>>,
+    pub models: Vec<(usize, Schedule<S>)>, // スレッド番号 (1から) と生成した表
     pub schedule_config: ScheduleConfig<SP, S, SS, DS>,
     pub total_time: Duration,
+    pub update_stats: Vec<Vec<UpdateStats>>, // 焼きなましの段階ごとの更新関数の統計
+    pub failures: Vec<ThreadFailure>,        // 生成に失敗したスレッド
+    pub logs: Vec<String>,                   // 生成器が報告するメッセージ
+    pub run_stats: Vec<ThreadStats>,         // スレッドごとの焼きなましの段階の統計
+    pub recombination: Option<RecombinationResult<S>>, // 組み換えを行った場合の結果
+}
+
+/// 組み換えの結果
+/// スレッドごとの結果とは別に、世代と子の番号とともに記録する
+#[derive(Debug, Clone)]
+pub struct RecombinationResult<S> {
+    pub models: Vec<(ModelId, Schedule<S>)>, // 最終世代に残った表 (resultのスコアの良い順)
+    pub update_stats: Vec<Vec<UpdateStats>>, // 組み換え後の焼きなましの段階ごとの更新関数の統計
+    pub failures: Vec<RecombinationFailure>, // 焼きなましに失敗した子
+    pub run_stats: Vec<ChildStats>,          // 子ごとの焼きなましの段階の統計
+}
+
+/// 表がどのスレッド、またはどの世代の子で作られたか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelId {
+    Thread(usize),                           // スレッド番号 (1から)
+    Child { generation: u32, child: usize }, // 世代と子の番号 (いずれも1から)
+}
+
+impl fmt::Display for ModelId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelId::Thread(thread) => write!(f, "thread {}", thread),
+            ModelId::Child { generation, child } => {
+                write!(f, "generation {} child {}", generation, child)
+            }
+        }
+    }
+}
+
+/// 組み換えの1つの子の焼きなましの段階ごとの統計
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChildStats {
+    pub id: ModelId,
+    pub stages: Vec<StageStats>,
+}
+
+/// 組み換えで焼きなましに失敗した子の情報
+/// 失敗した子は次の世代に残らない
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecombinationFailure {
+    pub id: ModelId,
+    pub stage: String, // 失敗した段階 (recombination annealing 1など)
+    pub message: String,
+}
+
+/// 1つのスレッドの焼きなましの段階ごとの統計
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadStats {
+    pub thread: usize,       // スレッド番号 (1から)
+    pub fill_score: Score,   // 埋めた直後の表のresultのスコア
+    pub fill_time: Duration, // 表を埋めるのにかかった時間
+    pub stages: Vec<StageStats>,
+}
+
+/// 焼きなましの1つの段階の統計
+/// scoreを除き、スコアはその段階のscore_functionsによるもの
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageStats {
+    pub name: String,                 // 段階の名前 (annealing 1など)
+    pub score: Score,                 // 段階後の表のresultのスコア
+    pub steps: u32,                   // 実行したステップ数
+    pub accepted: u32,                // 移動を受理したステップ数
+    pub improved: u32,                // 受理した移動でスコアが改善したステップ数
+    pub trace_interval: u32,          // best_score_traceを記録したステップの間隔
+    pub best_score_trace: Vec<Score>, // 段階の開始時と、trace_intervalステップごとと最後のステップ後の最良のスコア
+    pub time: Duration,               // 段階にかかった時間
+}
+
+/// 生成に失敗したスレッドの情報
+/// 失敗したスレッドの表はmodelsに含まれない
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadFailure {
+    pub schedule_config: String, // 勤務表configの名前
+    pub thread: usize,           // スレッド番号 (1から)
+    pub stage: String,           // 失敗した段階 (fill, annealing 1など)
+    pub message: String,
+}
+
+/// 更新関数ごとの統計
+/// 全スレッドの合計で、選択確率のみスレッドの平均
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateStats {
+    pub name: String,
+    pub used: u32,
+    pub accepted: u32,
+    pub improved: u32,
+    pub probability: f32,
 }
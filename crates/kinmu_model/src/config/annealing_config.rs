@@ -5,6 +5,60 @@ pub struct AnnealingConfig<SP> {
     pub seed: Option<u64>,    // 焼きなましのupdate関数の乱数のシード
     pub score_props: Vec<SP>, // 焼きなましのためのスコア
     pub update_func: String,
+    pub stage_type: StageType,  // 段階の種類
+    pub algorithm: Algorithm,   // 段階で用いる探索のアルゴリズム
+    pub acceptance: Acceptance, // 焼きなまし法で更新を受理する基準
+    pub adaptive_update: bool,  // 更新関数の選択確率を適応的に変更するか
     pub max_temp: f32,
     pub min_temp: f32,
+    pub auto_temperature: Option<AutoTemperature>, // 指定した場合、max_tempとmin_tempを焼きなましの直前に決める
+}
+
+/// 温度を自動で決める場合の、悪化する更新の目標の受理率
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoTemperature {
+    pub initial_acceptance: f32, // 最初のステップでの受理率
+    pub final_acceptance: f32,   // 最後のステップでの受理率
+}
+
+/// 焼きなましの段階の種類
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StageType {
+    /// update_funcで焼きなましを行う
+    #[default]
+    Annealing,
+    /// update_funcの近傍のうち最も良いものへの移動を、改善できなくなるまで繰り返す
+    /// stepは移動の回数の上限で、温度は用いない
+    Polish,
+}
+
+/// 焼きなましの段階で用いる探索のアルゴリズム
+/// いずれもupdate_funcで表を更新し、score_propsで評価する
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Algorithm {
+    /// 焼きなまし法
+    #[default]
+    Annealing,
+    /// タブーサーチ
+    /// 1ステップでcandidate_count個の候補を作り、最近変更した枠はtenureステップの間変更しない
+    Tabu { tenure: u32, candidate_count: u32 },
+    /// 山登り法
+    /// スコアが悪化しない更新のみ受理する
+    HillClimbing,
+}
+
+/// 焼きなまし法で更新後の表を受理する基準
+/// いずれも温度はtemperatureのbeginからendへステップに対して線形に変化する
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Acceptance {
+    /// スコアの悪化幅と温度から決まる確率で受理する
+    #[default]
+    Metropolis,
+    /// 温度を閾値として、悪化幅が閾値以下なら受理する
+    Threshold,
+    /// 温度を水位として、スコアが水位以下なら受理する
+    GreatDeluge,
+    /// length回前の更新時点のスコアより悪化しないなら受理する
+    /// 温度は用いない
+    LateAcceptance { length: u32 },
 }
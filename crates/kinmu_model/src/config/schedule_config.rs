@@ -1,5 +1,6 @@
 use super::super::{
-    Schedule, ScheduleState, Score, Staff, StaffAttributeName, StaffAttributeNameIndexMap, NG,
+    Availability, Pair, Schedule, ScheduleState, Score, Staff, StaffAttributeName,
+    StaffAttributeNameIndexMap, NG,
 };
 
 use std::collections::HashMap;
@@ -13,6 +14,26 @@ pub struct FillConfig {
     pub seed: Option<u64>,
 }
 
+/// スレッドごとの結果を交叉して、さらに焼きなましを行う組み換えに関するConfig
+#[derive(Clone, Debug, Default)]
+pub struct RecombinationConfig<SP> {
+    pub generations: u32,                            // 組み換えを繰り返す世代数
+    pub crossover: Crossover,                        // 交叉の方法
+    pub seed: Option<u64>,                           // 親の選択と交叉の乱数のシード
+    pub annealing_configs: Vec<AnnealingConfig<SP>>, // 交叉後に行う焼きなましの段階
+}
+
+/// 交叉の方法
+/// いずれも枠ごとにどちらかの親のシフトをそのまま用いる
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Crossover {
+    /// 職員ごとに親を選ぶ
+    #[default]
+    Staff,
+    /// バッファーを除いた日付を7日ごとに区切り、区切りごとに親を選ぶ
+    Week,
+}
+
 /// 結果の出力に関するConfig
 #[derive(Clone, Debug, Default)]
 pub struct ResultConfig<SP> {
@@ -40,6 +61,8 @@ pub struct StaffConfig {
     pub attribute_map: StaffAttributeNameIndexMap,
     pub list: Vec<Staff>,
     pub ng_list: Vec<NG>,
+    pub pair_list: Vec<Pair>,
+    pub availabilities: Vec<Availability>,
     pub count: usize,
 }
 
@@ -55,22 +78,71 @@ pub struct DayConfig<S, SS, DS> {
     pub requested_schedule: Schedule<S>,
     pub schedule_states: ScheduleState<SS>,
     pub attributes: HashMap<DayAttributeName, Vec<i32>>,
+    pub wishes: Vec<Wish<S>>,
+    pub shift_hours: Vec<(S, f32)>, // シフトごとの勤務時間 指定のないシフトは0時間
+}
+
+/// 職員の勤務の希望
+/// requested_scheduleと違い、叶わなくてもよい希望を優先度つきで表す
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Wish<S> {
+    pub staff: usize,
+    pub day: usize,
+    pub shift: S,
+    pub priority: Score,
 }
 
 /// 勤務表ごとのConfig
 #[derive(Clone, Debug, Default)]
 pub struct ScheduleConfig<SP, S, SS, DS> {
+    pub name: String, // 勤務表configの名前 (ファイルのパス)
     pub staff: StaffConfig,
     pub day: DayConfig<S, SS, DS>,
     pub fill: FillConfig,
     pub annealing_configs: Vec<AnnealingConfig<SP>>,
+    pub recombination: Option<RecombinationConfig<SP>>,
     pub result: ResultConfig<SP>,
 }
 
+impl<S: PartialEq, SS, DS> DayConfig<S, SS, DS> {
+    /// 指定したスタッフの希望のうち、叶った数と全体の数を取得する
+    pub fn count_granted_wishes(&self, staff: usize, schedule: &Schedule<S>) -> (usize, usize) {
+        let mut granted = 0;
+        let mut total = 0;
+        for wish in self.wishes.iter().filter(|w| w.staff == staff) {
+            total += 1;
+            if schedule[wish.staff][wish.day] == wish.shift {
+                granted += 1;
+            }
+        }
+        (granted, total)
+    }
+
+    /// 指定したシフトの勤務時間を取得する
+    /// 指定がない場合は0時間
+    pub fn get_shift_hours(&self, shift: &S) -> f32 {
+        self.shift_hours
+            .iter()
+            .find(|(s, _)| s == shift)
+            .map(|(_, h)| *h)
+            .unwrap_or(0.0)
+    }
+}
+
 impl StaffConfig {
     /// 指定したスタッフの指定したattributeを取得する
     pub fn get_attribute(&self, staff: usize, attribute: &StaffAttributeName) -> i32 {
         let att_index = self.attribute_map.name_to_index.get(attribute).unwrap();
         self.list[staff].attributes[*att_index]
     }
+
+    /// 指定したスタッフの1週間あたりの最大の勤務数を取得する
+    /// 指定がない場合はNone
+    pub fn get_max_shifts_per_week(&self, staff: usize) -> Option<i32> {
+        self.availabilities
+            .iter()
+            .filter(|a| a.staff == staff)
+            .filter_map(|a| a.max_shifts_per_week)
+            .min()
+    }
 }
@@ -1,4 +1,4 @@
-use super::{DayConfig, Schedule, StaffConfig};
+use super::{DayConfig, Schedule, ScheduleConfig, StaffConfig};
 
 /// スコアのエイリアス
 pub type Score = f32;
@@ -20,6 +20,26 @@ pub trait ScoreProp<S, SS, DS>: Sized {
         day_config: &DayConfig<S, SS, DS>,
         schedule: &Schedule<S>,
     ) -> Score;
+
+    /// 勤務表によらず避けられない評価値の下限を見積もる
+    /// 既定では見積もれないものとして負の無限大を返すため、下限の分かるスコアでは実装する
+    fn lower_bound(
+        &self,
+        _staff_config: &StaffConfig,
+        _day_config: &DayConfig<S, SS, DS>,
+    ) -> Score {
+        Score::NEG_INFINITY
+    }
+
+    /// 勤務表によらず満たせない条件を、利用者に示すメッセージとして返す
+    /// 既定では何も返さない
+    fn diagnose(
+        &self,
+        _staff_config: &StaffConfig,
+        _day_config: &DayConfig<S, SS, DS>,
+    ) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 /// mutでScorePropのVecを全て評価して和をとる
@@ -51,3 +71,34 @@ pub fn eval_scores_immut<SP: ScoreProp<S, SS, DS>, S, SS, DS>(
         .iter()
         .sum()
 }
+
+/// ScorePropのVecの評価値の下限の和をとる
+/// 下限を見積もれないScorePropを含む場合は負の無限大になる
+pub fn lower_bound_scores<SP: ScoreProp<S, SS, DS>, S, SS, DS>(
+    sps: &[SP],
+    staff_config: &StaffConfig,
+    day_config: &DayConfig<S, SS, DS>,
+) -> Score {
+    sps.iter()
+        .map(|sp| sp.lower_bound(staff_config, day_config))
+        .sum()
+}
+
+/// resultのスコアについて、勤務表によらず満たせない条件のメッセージを集める
+/// メッセージの先頭にはスコアの表示名を付ける
+pub fn diagnose_result<SP: ScoreProp<S, SS, DS>, S, SS, DS>(
+    schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+) -> Vec<String> {
+    schedule_config
+        .result
+        .score_functions
+        .iter()
+        .flat_map(|sf| {
+            sf.scores.iter().flat_map(|sp| {
+                sp.diagnose(&schedule_config.staff, &schedule_config.day)
+                    .into_iter()
+                    .map(|m| format!("警告: {}: {}", sf.display_name, m))
+            })
+        })
+        .collect()
+}
@@ -10,6 +10,20 @@ pub struct Staff {
 /// NGを管理する型
 pub type NG = (usize, usize);
 
+/// 一緒に勤務させたいペアを管理する型
+pub type Pair = (usize, usize);
+
+/// 職員の勤務可能な条件を管理する型
+/// 勤務できない日付や曜日ごとに入れてよいシフトは、読み込み時にShiftStateに変換される
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Availability {
+    pub staff: usize,
+    /// 勤務できない日付の範囲 両端を含む表の列の番号
+    pub unavailable_ranges: Vec<(usize, usize)>,
+    /// 1週間あたりの最大の勤務数
+    pub max_shifts_per_week: Option<i32>,
+}
+
 /// StaffAttribute名のエイリアス
 pub type StaffAttributeName = String;
 
@@ -2,10 +2,22 @@
 //! ファイル出力と標準出力は引数で切り替え
 
 use kinmu_core::Output;
-use kinmu_model::{eval_scores_immut, Answer, Schedule, ScheduleConfig, ScoreProp};
+use kinmu_model::{
+    eval_scores_immut, lower_bound_scores, Answer, RecombinationResult, Schedule, ScheduleConfig,
+    ScoreProp, StageStats, ThreadFailure, ThreadStats, UpdateStats,
+};
 
 use std::io;
 
+/// 収束のグラフの幅と高さ
+const CHART_WIDTH: f32 = 400.0;
+const CHART_HEIGHT: f32 = 150.0;
+
+/// 収束のグラフでスレッドごとに用いる線の色
+const CHART_COLORS: [&str; 6] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b",
+];
+
 /// テキスト出力の出力器
 /// outは出力先
 /// row_stats_shiftsは行の統計を表示するシフト
@@ -71,11 +83,23 @@ where
         SP: ScoreProp<S, SS, DS> + Clone,
         DS: ToJapanese,
     {
-        for (t, model) in ans.models.iter().enumerate() {
-            writeln!(self.out, "<div>thread: {}</div>", t + 1)?;
+        for log in &ans.logs {
+            writeln!(self.out, "<div>{}</div>", escape(log))?;
+        }
+        if !ans.logs.is_empty() {
+            writeln!(self.out)?;
+        }
+        for (thread, model) in &ans.models {
+            writeln!(self.out, "<div>thread: {}</div>", thread)?;
             self.write_model(&ans.schedule_config, model)?;
             writeln!(self.out, "<br/>")?;
         }
+        self.write_failures(&ans.failures)?;
+        self.write_update_stats(&ans.update_stats, "")?;
+        self.write_run_stats(&ans.run_stats)?;
+        if let Some(recombination) = &ans.recombination {
+            self.write_recombination(&ans.schedule_config, recombination)?;
+        }
         writeln!(self.out, "<div>total time: {:?}</div>", ans.total_time)?;
         writeln!(self.out)?;
         Ok(())
@@ -92,19 +116,28 @@ where
         SP: ScoreProp<S, SS, DS> + Clone,
         DS: ToJapanese,
     {
-        let score = eval_scores_immut(
-            &schedule_config
-                .result
-                .score_functions
-                .iter()
-                .flat_map(|x| x.scores.clone())
-                .collect::<Vec<SP>>(),
-            &schedule_config.staff,
-            &schedule_config.day,
-            model,
-        );
+        let scores = schedule_config
+            .result
+            .score_functions
+            .iter()
+            .flat_map(|x| x.scores.clone())
+            .collect::<Vec<SP>>();
+        let score = eval_scores_immut(&scores, &schedule_config.staff, &schedule_config.day, model);
+        // 表によらず避けられないペナルティの見積もりとの差
+        let lower_bound = lower_bound_scores(&scores, &schedule_config.staff, &schedule_config.day);
 
         writeln!(self.out, "<div>score: {}</div>", score)?;
+        if lower_bound.is_finite() {
+            writeln!(
+                self.out,
+                "<div>lower bound: {} (gap: {})</div>",
+                lower_bound,
+                score - lower_bound
+            )?;
+        } else {
+            // 負のスコアなど、下限を見積もれないスコアを含む
+            writeln!(self.out, "<div>lower bound: unknown</div>")?;
+        }
         self.write_schedule(schedule_config, model)?;
 
         writeln!(self.out)?;
@@ -143,6 +176,195 @@ where
 
         writeln!(self.out)?;
 
+        self.write_wishes(schedule_config, model)?;
+
+        Ok(())
+    }
+
+    /// 職員ごとに叶った希望の数を出力
+    /// 希望が1つもない場合は何も出力しない
+    fn write_wishes<SP, SS, DS>(
+        &mut self,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+        model: &Schedule<S>,
+    ) -> io::Result<()> {
+        if schedule_config.day.wishes.is_empty() {
+            return Ok(());
+        }
+
+        write!(self.out, "<table>")?;
+        write!(
+            self.out,
+            "<thead><tr><th scope=\"col\">人</th><th scope=\"col\">叶った希望</th></tr></thead>"
+        )?;
+        write!(self.out, "<tbody>")?;
+        for r in 0..schedule_config.staff.count {
+            let (granted, total) = schedule_config.day.count_granted_wishes(r, model);
+            if 0 < total {
+                write!(
+                    self.out,
+                    "<tr><th scope=\"row\">{}</th><td>{}/{}</td></tr>",
+                    schedule_config.staff.list[r].name, granted, total
+                )?;
+            }
+        }
+        write!(self.out, "</tbody>")?;
+        writeln!(self.out, "</table>")?;
+
+        Ok(())
+    }
+
+    /// 生成に失敗したスレッドを出力
+    fn write_failures(&mut self, failures: &[ThreadFailure]) -> io::Result<()> {
+        for f in failures {
+            writeln!(
+                self.out,
+                "<div style=\"white-space: pre-line;\">[エラー] 生成に失敗しました\n勤務表config: {}\nthread: {}\n段階: {}\n理由: {}\n</div>",
+                escape(&f.schedule_config),
+                f.thread,
+                escape(&f.stage),
+                escape(&f.message)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// 組み換えの結果を、スレッドごとの結果と区別して出力
+    fn write_recombination<SP, SS, DS>(
+        &mut self,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+        recombination: &RecombinationResult<S>,
+    ) -> io::Result<()>
+    where
+        S: Clone,
+        SP: ScoreProp<S, SS, DS> + Clone,
+        DS: ToJapanese,
+    {
+        for (id, model) in &recombination.models {
+            writeln!(self.out, "<div>recombination: {}</div>", id)?;
+            self.write_model(schedule_config, model)?;
+            writeln!(self.out, "<br/>")?;
+        }
+        for f in &recombination.failures {
+            writeln!(
+                self.out,
+                "<div style=\"white-space: pre-line;\">[エラー] 組み換えの焼きなましに失敗しました\n勤務表config: {}\nrecombination: {}\n段階: {}\n理由: {}\n</div>",
+                escape(&schedule_config.name),
+                f.id,
+                escape(&f.stage),
+                escape(&f.message)
+            )?;
+        }
+        self.write_update_stats(&recombination.update_stats, "recombination ")?;
+        let runs = recombination
+            .run_stats
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.id.to_string(), i + 1, c.stages.as_slice()))
+            .collect::<Vec<_>>();
+        self.write_stage_tables("recombination", &runs)?;
+
+        Ok(())
+    }
+
+    /// 焼きなましの段階ごとの更新関数の統計を出力
+    /// 段階の名前はprefixに続けてannealing 1などとする
+    /// 統計のない段階は出力しない
+    fn write_update_stats(
+        &mut self,
+        update_stats: &[Vec<UpdateStats>],
+        prefix: &str,
+    ) -> io::Result<()> {
+        for (i, stats) in update_stats.iter().enumerate() {
+            if stats.is_empty() {
+                continue;
+            }
+            writeln!(
+                self.out,
+                "<div>update stats ({}annealing {})</div>",
+                prefix,
+                i + 1
+            )?;
+            write!(self.out, "<table>")?;
+            write!(
+                self.out,
+                "<thead><tr><th scope=\"col\">更新関数</th><th scope=\"col\">選択</th><th scope=\"col\">受理</th><th scope=\"col\">改善</th><th scope=\"col\">選択確率</th></tr></thead>"
+            )?;
+            write!(self.out, "<tbody>")?;
+            for s in stats {
+                write!(
+                    self.out,
+                    "<tr><th scope=\"row\">{}</th><td>{}</td><td>{}</td><td>{}</td><td>{:.3}</td></tr>",
+                    s.name, s.used, s.accepted, s.improved, s.probability
+                )?;
+            }
+            write!(self.out, "</tbody>")?;
+            writeln!(self.out, "</table>")?;
+            writeln!(self.out, "<br/>")?;
+        }
+
+        Ok(())
+    }
+
+    /// 焼きなましの段階ごとに、スレッドごとのステップ数などの表と、最良のスコアの推移のグラフを出力
+    fn write_run_stats(&mut self, run_stats: &[ThreadStats]) -> io::Result<()> {
+        let runs = run_stats
+            .iter()
+            .map(|t| (t.thread.to_string(), t.thread, t.stages.as_slice()))
+            .collect::<Vec<_>>();
+        self.write_stage_tables("thread", &runs)
+    }
+
+    /// 焼きなましの段階ごとに、実行ごとのステップ数などの表と、最良のスコアの推移のグラフを出力
+    /// runsは実行の名前と線の色の番号、段階ごとの統計の組で、headerは名前の列の見出し
+    fn write_stage_tables(
+        &mut self,
+        header: &str,
+        runs: &[(String, usize, &[StageStats])],
+    ) -> io::Result<()> {
+        let stage_count = runs.iter().map(|r| r.2.len()).max().unwrap_or(0);
+        for i in 0..stage_count {
+            let stages = runs
+                .iter()
+                .filter_map(|(name, color, stages)| stages.get(i).map(|s| (name, *color, s)))
+                .collect::<Vec<_>>();
+            writeln!(
+                self.out,
+                "<div>run stats ({})</div>",
+                escape(&stages[0].2.name)
+            )?;
+            write!(self.out, "<table>")?;
+            write!(
+                self.out,
+                "<thead><tr><th scope=\"col\">{}</th><th scope=\"col\">ステップ</th><th scope=\"col\">受理</th><th scope=\"col\">改善</th><th scope=\"col\">最良のスコア</th><th scope=\"col\">段階後のスコア</th><th scope=\"col\">時間</th></tr></thead>",
+                header
+            )?;
+            write!(self.out, "<tbody>")?;
+            for (name, color, s) in &stages {
+                write!(
+                    self.out,
+                    "<tr><th scope=\"row\" style=\"color: {};\">{}</th><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:?}</td></tr>",
+                    chart_color(*color),
+                    name,
+                    s.steps,
+                    s.accepted,
+                    s.improved,
+                    s.best_score_trace.last().copied().unwrap_or_default(),
+                    s.score,
+                    s.time
+                )?;
+            }
+            write!(self.out, "</tbody>")?;
+            writeln!(self.out, "</table>")?;
+            let chart = stages
+                .iter()
+                .map(|(_, color, s)| (*color, *s))
+                .collect::<Vec<_>>();
+            writeln!(self.out, "{}", convergence_chart(&chart))?;
+            writeln!(self.out, "<br/>")?;
+        }
+
         Ok(())
     }
 
@@ -344,7 +566,6 @@ where
         }..schedule_config.day.count
         {
             let mut sum = 0;
-            #[allow(clippy::needless_range_loop)]
             for r in 0..schedule_config.staff.count {
                 if schedule[r][c] == self.column_stats_shifts[index] {
                     sum += 1;
@@ -389,7 +610,116 @@ td {{
     }
 }
 
+/// HTMLで特別な意味を持つ文字を文字参照に置き換える
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// スレッド番号に対応する線の色
+fn chart_color(thread: usize) -> &'static str {
+    CHART_COLORS[(thread + CHART_COLORS.len() - 1) % CHART_COLORS.len()]
+}
+
+/// スレッドごとの最良のスコアの推移を折れ線で表したSVGを生成する
+/// 横軸はステップ、縦軸はスコアで、上端と下端にスコアの最大値と最小値を表示する
+fn convergence_chart(stages: &[(usize, &StageStats)]) -> String {
+    let scores = stages
+        .iter()
+        .flat_map(|(_, s)| s.best_score_trace.iter().copied());
+    let max_score = scores.clone().fold(f32::MIN, f32::max);
+    let min_score = scores.fold(f32::MAX, f32::min);
+    let score_range = if max_score > min_score {
+        max_score - min_score
+    } else {
+        1.0
+    };
+    let max_step = stages
+        .iter()
+        .map(|(_, s)| s.steps)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" style=\"border: 1px solid black;\">",
+        CHART_WIDTH, CHART_HEIGHT
+    );
+    for (t, s) in stages {
+        let points = s
+            .best_score_trace
+            .iter()
+            .enumerate()
+            .map(|(i, score)| {
+                // 最後の点は、間隔の倍数でない最後のステップの場合がある
+                let x = (i as u32 * s.trace_interval).min(s.steps) as f32 / max_step * CHART_WIDTH;
+                let y = (max_score - score) / score_range * (CHART_HEIGHT - 20.0) + 10.0;
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        svg += &format!(
+            "<polyline fill=\"none\" stroke=\"{}\" points=\"{}\"/>",
+            chart_color(*t),
+            points
+        );
+    }
+    svg += &format!(
+        "<text x=\"2\" y=\"10\" font-size=\"10\">{}</text><text x=\"2\" y=\"{}\" font-size=\"10\">{}</text>",
+        max_score,
+        CHART_HEIGHT - 2.0,
+        min_score
+    );
+    svg += "</svg>";
+    svg
+}
+
 // HTML出力のためのToString
 pub trait ToJapanese {
     fn to_japanese(&self) -> String;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq)]
+    struct TestShift;
+
+    impl ToJapanese for TestShift {
+        fn to_japanese(&self) -> String {
+            String::new()
+        }
+    }
+
+    /// 失敗の理由や段階に含まれるHTMLの特殊文字がエスケープされるケース
+    #[test]
+    fn test_write_failures_escape() {
+        let mut out = Vec::new();
+        let mut output = OutputHTML::<_, TestShift>::new(&mut out, false, Vec::new(), Vec::new());
+        output
+            .write_failures(&[ThreadFailure {
+                schedule_config: String::from("a&b.toml"),
+                thread: 3,
+                stage: String::from("<fill>"),
+                message: String::from("panicked: \"<script>\""),
+            }])
+            .unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains("勤務表config: a&amp;b.toml"));
+        assert!(html.contains("thread: 3"));
+        assert!(html.contains("段階: &lt;fill&gt;"));
+        assert!(html.contains("理由: panicked: &quot;&lt;script&gt;&quot;"));
+        assert!(!html.contains("<script>"));
+    }
+}
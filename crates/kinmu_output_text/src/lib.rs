@@ -2,7 +2,10 @@
 //! ファイル出力と標準出力は引数で切り替え
 
 use kinmu_core::Output;
-use kinmu_model::{eval_scores_immut, Answer, Schedule, ScheduleConfig, ScoreProp};
+use kinmu_model::{
+    eval_scores_immut, lower_bound_scores, Answer, RecombinationResult, Schedule, ScheduleConfig,
+    ScoreProp, StageStats, ThreadFailure, ThreadStats, UpdateStats,
+};
 
 use std::fmt;
 use std::io;
@@ -73,10 +76,22 @@ where
         SP: ScoreProp<S, SS, DS> + Clone,
         DS: fmt::Display,
     {
-        for (t, model) in ans.models.iter().enumerate() {
-            writeln!(self.out, "thread: {}", t + 1)?;
+        for log in &ans.logs {
+            writeln!(self.out, "{}", log)?;
+        }
+        if !ans.logs.is_empty() {
+            writeln!(self.out)?;
+        }
+        for (thread, model) in &ans.models {
+            writeln!(self.out, "thread: {}", thread)?;
             self.write_model(&ans.schedule_config, model)?;
         }
+        self.write_failures(&ans.failures)?;
+        self.write_update_stats(&ans.update_stats, "")?;
+        self.write_run_stats(&ans.run_stats)?;
+        if let Some(recombination) = &ans.recombination {
+            self.write_recombination(&ans.schedule_config, recombination)?;
+        }
         writeln!(self.out, "total time: {:?}", ans.total_time)?;
         writeln!(self.out)?;
         Ok(())
@@ -93,19 +108,28 @@ where
         SP: ScoreProp<S, SS, DS> + Clone,
         DS: fmt::Display,
     {
-        let score = eval_scores_immut(
-            &schedule_config
-                .result
-                .score_functions
-                .iter()
-                .flat_map(|x| x.scores.clone())
-                .collect::<Vec<SP>>(),
-            &schedule_config.staff,
-            &schedule_config.day,
-            model,
-        );
+        let scores = schedule_config
+            .result
+            .score_functions
+            .iter()
+            .flat_map(|x| x.scores.clone())
+            .collect::<Vec<SP>>();
+        let score = eval_scores_immut(&scores, &schedule_config.staff, &schedule_config.day, model);
+        // 表によらず避けられないペナルティの見積もりとの差
+        let lower_bound = lower_bound_scores(&scores, &schedule_config.staff, &schedule_config.day);
 
         writeln!(self.out, "score: {}", score)?;
+        if lower_bound.is_finite() {
+            writeln!(
+                self.out,
+                "lower bound: {} (gap: {})",
+                lower_bound,
+                score - lower_bound
+            )?;
+        } else {
+            // 負のスコアなど、下限を見積もれないスコアを含む
+            writeln!(self.out, "lower bound: unknown")?;
+        }
         self.write_schedule(schedule_config, model)?;
 
         writeln!(self.out)?;
@@ -146,6 +170,153 @@ where
 
         writeln!(self.out)?;
 
+        self.write_wishes(schedule_config, model)?;
+
+        Ok(())
+    }
+
+    /// 職員ごとに叶った希望の数を出力
+    /// 希望が1つもない場合は何も出力しない
+    fn write_wishes<SP, SS, DS>(
+        &mut self,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+        model: &Schedule<S>,
+    ) -> io::Result<()> {
+        if schedule_config.day.wishes.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(self.out, "granted wishes:")?;
+        for r in 0..schedule_config.staff.count {
+            let (granted, total) = schedule_config.day.count_granted_wishes(r, model);
+            if 0 < total {
+                writeln!(
+                    self.out,
+                    "{} : {}/{}",
+                    schedule_config.staff.list[r].name, granted, total
+                )?;
+            }
+        }
+
+        writeln!(self.out)?;
+
+        Ok(())
+    }
+
+    /// 生成に失敗したスレッドを出力
+    fn write_failures(&mut self, failures: &[ThreadFailure]) -> io::Result<()> {
+        for f in failures {
+            kinmu_color::write(
+                self.out,
+                "[エラー]",
+                kinmu_color::Color::Red,
+                self.use_color,
+            )?;
+            writeln!(self.out, " 生成に失敗しました")?;
+            writeln!(self.out, "勤務表config: {}", f.schedule_config)?;
+            writeln!(self.out, "thread: {}", f.thread)?;
+            writeln!(self.out, "段階: {}", f.stage)?;
+            writeln!(self.out, "理由: {}", f.message)?;
+            writeln!(self.out)?;
+        }
+
+        Ok(())
+    }
+
+    /// 組み換えの結果を、スレッドごとの結果と区別して出力
+    fn write_recombination<SP, SS, DS>(
+        &mut self,
+        schedule_config: &ScheduleConfig<SP, S, SS, DS>,
+        recombination: &RecombinationResult<S>,
+    ) -> io::Result<()>
+    where
+        S: Clone,
+        SP: ScoreProp<S, SS, DS> + Clone,
+        DS: fmt::Display,
+    {
+        for (id, model) in &recombination.models {
+            writeln!(self.out, "recombination: {}", id)?;
+            self.write_model(schedule_config, model)?;
+        }
+        for f in &recombination.failures {
+            kinmu_color::write(
+                self.out,
+                "[エラー]",
+                kinmu_color::Color::Red,
+                self.use_color,
+            )?;
+            writeln!(self.out, " 組み換えの焼きなましに失敗しました")?;
+            writeln!(self.out, "勤務表config: {}", schedule_config.name)?;
+            writeln!(self.out, "recombination: {}", f.id)?;
+            writeln!(self.out, "段階: {}", f.stage)?;
+            writeln!(self.out, "理由: {}", f.message)?;
+            writeln!(self.out)?;
+        }
+        self.write_update_stats(&recombination.update_stats, "recombination ")?;
+        for c in &recombination.run_stats {
+            writeln!(self.out, "run stats ({}):", c.id)?;
+            self.write_stage_stats(&c.stages)?;
+            writeln!(self.out)?;
+        }
+
+        Ok(())
+    }
+
+    /// 焼きなましの段階ごとの更新関数の統計を出力
+    /// 段階の名前はprefixに続けてannealing 1などとする
+    /// 統計のない段階は出力しない
+    fn write_update_stats(
+        &mut self,
+        update_stats: &[Vec<UpdateStats>],
+        prefix: &str,
+    ) -> io::Result<()> {
+        for (i, stats) in update_stats.iter().enumerate() {
+            if stats.is_empty() {
+                continue;
+            }
+            writeln!(self.out, "update stats ({}annealing {}):", prefix, i + 1)?;
+            for s in stats {
+                writeln!(
+                    self.out,
+                    "{} : used {}, accepted {}, improved {}, probability {:.3}",
+                    s.name, s.used, s.accepted, s.improved, s.probability
+                )?;
+            }
+            writeln!(self.out)?;
+        }
+
+        Ok(())
+    }
+
+    /// スレッドごとに、埋めた直後と各段階の後のresultのスコア、かかった時間と、
+    /// 焼きなましの段階のステップ数や最良のスコアの推移を出力
+    fn write_run_stats(&mut self, run_stats: &[ThreadStats]) -> io::Result<()> {
+        for t in run_stats {
+            writeln!(self.out, "run stats (thread {}):", t.thread)?;
+            writeln!(
+                self.out,
+                "fill : score {}, time {:?}",
+                t.fill_score, t.fill_time
+            )?;
+            self.write_stage_stats(&t.stages)?;
+            writeln!(self.out)?;
+        }
+
+        Ok(())
+    }
+
+    /// 焼きなましの段階ごとのステップ数や最良のスコアの推移を1行ずつ出力
+    fn write_stage_stats(&mut self, stages: &[StageStats]) -> io::Result<()> {
+        for s in stages {
+            let first = s.best_score_trace.first().copied().unwrap_or_default();
+            let last = s.best_score_trace.last().copied().unwrap_or_default();
+            writeln!(
+                self.out,
+                "{} : score {}, time {:?}, steps {}, accepted {}, improved {}, best score {} -> {}",
+                s.name, s.score, s.time, s.steps, s.accepted, s.improved, first, last
+            )?;
+        }
+
         Ok(())
     }
 
@@ -268,7 +439,6 @@ where
         let mut max_length = 0;
         for c in 0..schedule_config.day.count {
             let mut sum = 0;
-            #[allow(clippy::needless_range_loop)]
             for r in 0..schedule_config.staff.count {
                 if schedule[r][c] == self.column_stats_shifts[index] {
                     sum += 1;
@@ -1,6 +1,9 @@
+use kinmu::core::Generator;
 use kinmu::generator_with_annealing::GeneratorWithAnnealing;
+use kinmu::generator_with_solver::GeneratorWithSolver;
 use kinmu::input_by_file::InputByFile;
-use kinmu::lib::{Shift, StdFill, StdUpdate};
+use kinmu::lib::{DayState, Shift, ShiftState, StdFill, StdScoreProp, StdUpdate};
+use kinmu::model::{Answer, MainConfig};
 
 use kinmu::OutputTextOrHTML;
 
@@ -8,6 +11,7 @@ use getopts::Options;
 use std::env;
 use std::fs::OpenOptions;
 use std::io;
+use std::time::Duration;
 
 /// 引数で指定しない場合に読み込むmain_configのパス
 const DEFALUT_MAIN_CONFIG_PATH: &str = "example/simple_case/main_config.toml";
@@ -41,6 +45,12 @@ fn main() -> io::Result<()> {
     opts.optflag("h", "help", "show help ヘルプを表示");
     opts.optflag("v", "version", "show version バージョンを表示");
     opts.optflag("", "html", "htmlを出力");
+    opts.optopt(
+        "",
+        "solver",
+        "use exact solver with time limit; positive StaffCount, StaffCountAtLeast, PatternFixed and NGPair scores become hard constraints ソルバーを制限時間(秒)を指定して使用 正のStaffCount, StaffCountAtLeast, PatternFixed, NGPairは必ず満たす制約になる",
+        "SECONDS",
+    );
 
     let mut out: Box<dyn io::Write> = Box::new(io::stdout());
     let mut use_color = true;
@@ -100,10 +110,33 @@ fn main() -> io::Result<()> {
     // html出力を選択
     let use_html = matches.opt_present("html");
 
+    // 生成器を選択
+    #[allow(clippy::type_complexity)]
+    let mut generator: Box<
+        dyn Generator<
+            MainConfig<StdScoreProp, Shift, ShiftState, DayState>,
+            Vec<Answer<StdScoreProp, Shift, ShiftState, DayState>>,
+        >,
+    > = match matches.opt_str("solver") {
+        Some(x) => match x.parse::<f64>() {
+            Ok(seconds) if 0.0 <= seconds => Box::new(GeneratorWithSolver::new(
+                StdFill,
+                StdUpdate,
+                Duration::from_secs_f64(seconds),
+            )),
+            _ => {
+                kinmu::color::write(&mut out, "[エラー]", kinmu::color::Color::Red, use_color)?;
+                writeln!(out, " solverの制限時間が不正です: {}", x)?;
+                return Ok(());
+            }
+        },
+        None => Box::new(GeneratorWithAnnealing::new(StdFill, StdUpdate)),
+    };
+
     // 実行
     match kinmu::core::run(
-        &mut InputByFile::new(main_config_path),
-        &mut GeneratorWithAnnealing::new(StdFill, StdUpdate),
+        &mut InputByFile::new(main_config_path, StdFill, StdUpdate),
+        generator.as_mut(),
         {
             use Shift::*;
             &mut OutputTextOrHTML::new(